@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use bevy::ecs::system::CommandQueue;
+use bevy::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use moonbase_online::buildings::{Building, BuildingKind, BuildingStatus, PowerCable};
+use moonbase_online::leveling::{self, LevelingJob};
+use moonbase_online::map::TileMap;
+use moonbase_online::mission::{self, Mission};
+use moonbase_online::operations;
+use moonbase_online::simulation::{self, NewDay, SECONDS_PER_DAY};
+
+fn mission_of_size(size: u32) -> Mission {
+    let mut mission = Mission::load(1).unwrap().with_seed(42);
+    mission.map_size = (size, size);
+    mission
+}
+
+/// Start a mission in `world`, leaving the same state `setup_mission` does
+fn start(world: &mut World, size: u32) {
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, world);
+    mission::start_mission(&mut commands, mission_of_size(size), None);
+    queue.apply(world);
+}
+
+fn started_world(size: u32) -> World {
+    let mut world = World::new();
+    start(&mut world, size);
+    world
+}
+
+fn bench_setup_mission(c: &mut Criterion) {
+    let mut group = c.benchmark_group("setup_mission");
+    group.sample_size(10);
+    for size in [64, 256, 1024] {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{size}x{size}")), &size, |b, &size| {
+            b.iter(|| started_world(size));
+        });
+    }
+    group.finish();
+}
+
+fn bench_tile_lookup(c: &mut Criterion) {
+    let world = started_world(256);
+    let tile_map = world.resource::<TileMap>();
+
+    c.bench_function("tile_lookup/world_to_surface", |b| {
+        b.iter(|| {
+            let mut buildable = 0;
+            for y in 0..tile_map.height {
+                for x in 0..tile_map.width {
+                    let world_pos = Vec2::new(x as f32 * 10.0 + 3.0, y as f32 * 10.0 - 3.0);
+                    let (tx, ty) = tile_map.tile_at(black_box(world_pos)).unwrap();
+                    if tile_map.surface(tx, ty).is_some_and(|(_, slope)| slope < 0.35) {
+                        buildable += 1;
+                    }
+                }
+            }
+            buildable
+        });
+    });
+
+    c.bench_function("tile_lookup/neighbors8", |b| {
+        b.iter(|| {
+            let mut total = 0.0;
+            for y in 0..tile_map.height {
+                for x in 0..tile_map.width {
+                    total += tile_map
+                        .neighbors8(x, y)
+                        .filter_map(|(nx, ny)| tile_map.elevation(nx, ny))
+                        .sum::<f32>();
+                }
+            }
+            total
+        });
+    });
+}
+
+/// One game day on a 256x256 base with labs, mines, solar arrays, cables and
+/// leveling jobs that never finish, so every tick does the same work
+fn bench_simulation_tick(c: &mut Criterion) {
+    let mut app = App::new();
+    start(&mut app.world, 256);
+    app.add_event::<NewDay>().add_systems(
+        Update,
+        (
+            simulation::advance_clock,
+            leveling::assign_workers,
+            operations::update_building_status,
+            simulation::accumulate_research,
+            operations::run_production,
+            operations::pay_upkeep,
+            leveling::progress_leveling,
+        )
+            .chain(),
+    );
+
+    let kinds = [
+        BuildingKind::LivingModule,
+        BuildingKind::OxygenPlant,
+        BuildingKind::ScienceLab,
+        BuildingKind::Telescope,
+        BuildingKind::HeliumMine,
+        BuildingKind::SolarArray,
+    ];
+    for i in 0..200u32 {
+        let (x, y) = (10 + (i % 20) * 4, 10 + (i / 20) * 4);
+        app.world.spawn((Building::new(kinds[i as usize % kinds.len()], x, y, 0.0), BuildingStatus::default()));
+        app.world.spawn(PowerCable { x: x + 1, y, cost: 0.0 });
+    }
+    for i in 0..50u32 {
+        app.world.spawn(LevelingJob::new(150 + i, 150, f32::MAX));
+    }
+
+    // A fixed day-long delta, so every update advances the clock by one day
+    let mut time = Time::<()>::default();
+    time.advance_by(Duration::from_secs_f32(SECONDS_PER_DAY));
+    app.world.insert_resource(time);
+
+    c.bench_function("simulation_tick/populated_base", |b| {
+        b.iter(|| app.update());
+    });
+}
+
+criterion_group!(benches, bench_setup_mission, bench_tile_lookup, bench_simulation_tick);
+criterion_main!(benches);
@@ -0,0 +1,459 @@
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::Tool;
+use crate::map::TileMap;
+use crate::mission::TerrainType;
+use crate::terrain::travel_cost;
+
+pub const CABLE_COST_PER_TILE: f32 = 1_000.0;
+
+/// Steepest grade a structure can be founded on
+pub const MAX_BUILD_SLOPE: f32 = 0.35;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BuildingKind {
+    LivingModule,
+    OxygenPlant,
+    ScienceLab,
+    Telescope,
+    HeliumMine,
+    SolarArray,
+    LandingPad,
+}
+
+/// Goods a running building turns out every day
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Product {
+    Oxygen,
+    Research,
+    Helium3,
+}
+
+impl Product {
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Product::Oxygen => "kg oxygen",
+            Product::Research => "research points",
+            Product::Helium3 => "g helium-3",
+        }
+    }
+}
+
+/// Helium-3 content at which a mine yields its rated output
+pub const RATED_HELIUM3_PPB: f32 = 20.0;
+
+pub const MAX_LEVEL: u32 = 3;
+
+/// Share of the construction price paid out every day in upkeep
+const UPKEEP_RATE: f32 = 0.002;
+
+/// Share of the money sunk into a building that demolition gets back
+pub const DEMOLITION_REFUND: f32 = 0.5;
+
+impl BuildingKind {
+    pub fn from_tool(tool: Tool) -> Option<Self> {
+        match tool {
+            Tool::LivingModule => Some(BuildingKind::LivingModule),
+            Tool::OxygenPlant => Some(BuildingKind::OxygenPlant),
+            Tool::ScienceLab => Some(BuildingKind::ScienceLab),
+            Tool::Telescope => Some(BuildingKind::Telescope),
+            Tool::HeliumMine => Some(BuildingKind::HeliumMine),
+            Tool::SolarArray => Some(BuildingKind::SolarArray),
+            Tool::LandingPad => Some(BuildingKind::LandingPad),
+            Tool::None | Tool::PowerCable | Tool::Level | Tool::Bulldoze => None,
+        }
+    }
+
+    pub fn base_cost(&self) -> f32 {
+        match self {
+            BuildingKind::LivingModule => 50_000.0,
+            BuildingKind::OxygenPlant => 80_000.0,
+            BuildingKind::ScienceLab => 120_000.0,
+            BuildingKind::Telescope => 150_000.0,
+            BuildingKind::HeliumMine => 200_000.0,
+            BuildingKind::SolarArray => 60_000.0,
+            BuildingKind::LandingPad => 150_000.0,
+        }
+    }
+
+    /// Work days to put up on flat ground with a bulldozer on the job
+    pub fn build_days(&self) -> f32 {
+        match self {
+            BuildingKind::LivingModule => 4.0,
+            BuildingKind::OxygenPlant => 6.0,
+            BuildingKind::ScienceLab => 8.0,
+            BuildingKind::Telescope => 5.0,
+            BuildingKind::HeliumMine => 10.0,
+            BuildingKind::SolarArray => 3.0,
+            BuildingKind::LandingPad => 6.0,
+        }
+    }
+
+    /// Tonnes of materials hauled from the lander when the site has no power
+    pub fn materials(&self) -> f32 {
+        match self {
+            BuildingKind::LivingModule => 20.0,
+            BuildingKind::OxygenPlant => 30.0,
+            BuildingKind::ScienceLab => 25.0,
+            BuildingKind::Telescope => 10.0,
+            BuildingKind::HeliumMine => 40.0,
+            BuildingKind::SolarArray => 15.0,
+            BuildingKind::LandingPad => 35.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuildingKind::LivingModule => "Living Module",
+            BuildingKind::OxygenPlant => "Oxygen Plant",
+            BuildingKind::ScienceLab => "Science Lab",
+            BuildingKind::Telescope => "Telescope",
+            BuildingKind::HeliumMine => "Helium Mine",
+            BuildingKind::SolarArray => "Solar Array",
+            BuildingKind::LandingPad => "Landing Pad",
+        }
+    }
+
+    /// Power in kW: positive for generators, negative for consumers
+    pub fn power(&self) -> f32 {
+        match self {
+            BuildingKind::LivingModule => -10.0,
+            BuildingKind::OxygenPlant => -40.0,
+            BuildingKind::ScienceLab => -30.0,
+            BuildingKind::Telescope => -20.0,
+            BuildingKind::HeliumMine => -60.0,
+            BuildingKind::SolarArray => 100.0,
+            BuildingKind::LandingPad => -15.0,
+        }
+    }
+
+    /// Crew needed on shift to run it
+    pub fn crew(&self) -> u32 {
+        match self {
+            BuildingKind::LivingModule | BuildingKind::SolarArray => 0,
+            BuildingKind::Telescope | BuildingKind::LandingPad => 1,
+            BuildingKind::OxygenPlant => 2,
+            BuildingKind::ScienceLab => 3,
+            BuildingKind::HeliumMine => 4,
+        }
+    }
+
+    /// Crew it can house
+    pub fn housing(&self) -> u32 {
+        match self {
+            BuildingKind::LivingModule => 4,
+            _ => 0,
+        }
+    }
+
+    /// How much of its product it can hold before output is wasted
+    pub fn storage(&self) -> f32 {
+        match self.product() {
+            Some((Product::Oxygen, _)) => 500.0,
+            Some((Product::Helium3, _)) => 100.0,
+            Some((Product::Research, _)) | None => 0.0,
+        }
+    }
+
+    /// What it makes and how much per day at full rate
+    pub fn product(&self) -> Option<(Product, f32)> {
+        match self {
+            BuildingKind::OxygenPlant => Some((Product::Oxygen, 50.0)),
+            BuildingKind::ScienceLab => Some((Product::Research, 5.0)),
+            BuildingKind::Telescope => Some((Product::Research, 2.0)),
+            BuildingKind::HeliumMine => Some((Product::Helium3, 10.0)),
+            BuildingKind::LivingModule | BuildingKind::SolarArray | BuildingKind::LandingPad => None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            BuildingKind::LivingModule => Color::ORANGE,
+            BuildingKind::OxygenPlant => Color::CYAN,
+            BuildingKind::ScienceLab => Color::GREEN,
+            BuildingKind::Telescope => Color::PURPLE,
+            BuildingKind::HeliumMine => Color::RED,
+            BuildingKind::SolarArray => Color::YELLOW_GREEN,
+            BuildingKind::LandingPad => Color::SILVER,
+        }
+    }
+}
+
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct Building {
+    pub kind: BuildingKind,
+    pub x: u32,
+    pub y: u32,
+    /// Condition in percent; 0 means wrecked
+    pub health: f32,
+    /// Switched-off buildings draw no power, need no crew and cost less upkeep
+    pub enabled: bool,
+    /// Broken-down buildings stop working until repaired
+    pub broken: bool,
+    pub level: u32,
+    /// Product held on site
+    pub stored: f32,
+    /// Money spent building and upgrading it
+    pub invested: f32,
+}
+
+impl Building {
+    pub fn new(kind: BuildingKind, x: u32, y: u32, cost: f32) -> Self {
+        Self { kind, x, y, health: 100.0, enabled: true, broken: false, level: 1, stored: 0.0, invested: cost }
+    }
+
+    /// Switched on and not broken down
+    pub fn operating(&self) -> bool {
+        self.enabled && !self.broken
+    }
+
+    /// Each upgrade adds half the original capacity
+    pub fn level_factor(&self) -> f32 {
+        1.0 + 0.5 * (self.level - 1) as f32
+    }
+
+    pub fn power(&self) -> f32 {
+        self.kind.power() * self.level_factor()
+    }
+
+    pub fn storage(&self) -> f32 {
+        self.kind.storage() * self.level_factor()
+    }
+
+    /// Daily running cost
+    pub fn upkeep(&self) -> f32 {
+        let upkeep = self.kind.base_cost() * UPKEEP_RATE * self.level_factor();
+        if self.enabled { upkeep } else { upkeep * 0.25 }
+    }
+
+    pub fn upgrade_cost(&self) -> Option<f32> {
+        (self.level < MAX_LEVEL).then(|| self.kind.base_cost() * 0.5 * self.level as f32)
+    }
+
+    pub fn repair_cost(&self) -> f32 {
+        self.kind.base_cost() * 0.3 * (100.0 - self.health) / 100.0
+    }
+
+    /// Back to full health and working order
+    pub fn repair(&mut self) {
+        self.health = 100.0;
+        self.broken = false;
+    }
+
+    pub fn demolition_refund(&self) -> f32 {
+        self.invested * DEMOLITION_REFUND * self.health / 100.0
+    }
+}
+
+/// Ask for a building or cable segment to be torn down and part of its cost
+/// refunded; see `operations::demolish`
+#[derive(Event)]
+pub struct Demolish {
+    pub entity: Entity,
+}
+
+/// How a building is running, refreshed by `operations::update_building_status`
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq)]
+pub struct BuildingStatus {
+    pub powered: bool,
+    pub staffed: bool,
+    /// Daily output of its product at the current status
+    pub output: f32,
+}
+
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct PowerCable {
+    pub x: u32,
+    pub y: u32,
+    /// Price paid for this segment
+    pub cost: f32,
+}
+
+/// Price of founding something on a tile, or `None` if it can't be built there.
+/// Steep ground needs terracing and anchoring, so cost rises with slope.
+pub fn construction_cost(base_cost: f32, terrain: TerrainType, slope: f32) -> Option<f32> {
+    if slope > MAX_BUILD_SLOPE {
+        return None;
+    }
+    let terrain_factor = match terrain {
+        TerrainType::Flat => 1.0,
+        TerrainType::Rough => 1.5,
+        TerrainType::Crater => 2.5,
+        TerrainType::Mountain => return None,
+    };
+    Some(base_cost * terrain_factor * (1.0 + slope * 4.0))
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct Frontier {
+    cost: f32,
+    tile: (u32, u32),
+}
+
+impl Eq for Frontier {}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the heap pops the cheapest tile first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Cheapest 4-connected route between two tiles, weighted by `travel_cost`.
+pub fn route_cable(start: (u32, u32), end: (u32, u32), tile_map: &TileMap) -> Option<Vec<(u32, u32)>> {
+    let step_cost = |&(x, y): &(u32, u32)| {
+        tile_map
+            .surface(x, y)
+            .and_then(|(terrain, slope)| travel_cost(terrain, slope))
+    };
+    step_cost(&start)?;
+    step_cost(&end)?;
+
+    let mut best: HashMap<(u32, u32), f32> = HashMap::from([(start, 0.0)]);
+    let mut came_from: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
+    let mut frontier = BinaryHeap::from([Frontier { cost: 0.0, tile: start }]);
+
+    while let Some(Frontier { cost, tile }) = frontier.pop() {
+        if tile == end {
+            let mut path = vec![end];
+            let mut current = end;
+            while let Some(&previous) = came_from.get(&current) {
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if cost > best[&tile] {
+            continue;
+        }
+
+        for next in tile_map.neighbors4(tile.0, tile.1) {
+            if let Some(step) = step_cost(&next) {
+                let next_cost = cost + step;
+                if best.get(&next).map_or(true, |&known| next_cost < known) {
+                    best.insert(next, next_cost);
+                    came_from.insert(next, tile);
+                    frontier.push(Frontier { cost: next_cost, tile: next });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+pub fn spawn_building(commands: &mut Commands, building: Building) -> Entity {
+    let transform = Transform::from_xyz(building.x as f32 * 10.0, building.y as f32 * 10.0, 1.0);
+    commands
+        .spawn((building, BuildingStatus::default(), SpatialBundle { transform, ..default() }))
+        .id()
+}
+
+pub fn spawn_cable(commands: &mut Commands, cable: PowerCable) -> Entity {
+    let transform = Transform::from_xyz(cable.x as f32 * 10.0, cable.y as f32 * 10.0, 0.5);
+    commands.spawn((cable, SpatialBundle { transform, ..default() })).id()
+}
+
+pub fn draw_structures(
+    mut gizmos: Gizmos,
+    buildings: Query<&Building>,
+    cables: Query<&PowerCable>,
+) {
+    for cable in cables.iter() {
+        gizmos.rect_2d(
+            Vec2::new(cable.x as f32 * 10.0, cable.y as f32 * 10.0),
+            0.0,
+            Vec2::new(3.0, 3.0),
+            Color::YELLOW,
+        );
+    }
+    for building in buildings.iter() {
+        let center = Vec2::new(building.x as f32 * 10.0, building.y as f32 * 10.0);
+        gizmos.rect_2d(center, 0.0, Vec2::new(7.0, 7.0), building.kind.color());
+        gizmos.circle_2d(center, 2.0, building.kind.color());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mission::MapTile;
+
+    fn set(map: &mut TileMap, (x, y): (u32, u32), terrain: TerrainType, slope: f32) {
+        map.set(&MapTile { x, y, terrain, elevation: 0.0, slope, helium3: 0.0 });
+    }
+
+    #[test]
+    fn test_construction_cost_rises_with_slope() {
+        let level = construction_cost(1000.0, TerrainType::Flat, 0.0).unwrap();
+        let sloped = construction_cost(1000.0, TerrainType::Flat, 0.2).unwrap();
+        assert_eq!(level, 1000.0);
+        assert!(sloped > level);
+        assert!(construction_cost(1000.0, TerrainType::Rough, 0.0).unwrap() > level);
+        assert_eq!(construction_cost(1000.0, TerrainType::Flat, 0.5), None);
+        assert_eq!(construction_cost(1000.0, TerrainType::Mountain, 0.0), None);
+    }
+
+    #[test]
+    fn test_building_levels() {
+        let mut lab = Building::new(BuildingKind::ScienceLab, 0, 0, 120_000.0);
+        assert_eq!(lab.power(), -30.0);
+        assert_eq!(lab.repair_cost(), 0.0);
+        assert!(lab.upgrade_cost().is_some());
+
+        lab.level = MAX_LEVEL;
+        assert_eq!(lab.power(), -60.0);
+        assert_eq!(lab.upgrade_cost(), None);
+
+        let running = lab.upkeep();
+        lab.enabled = false;
+        assert!(lab.upkeep() < running);
+
+        lab.health = 50.0;
+        assert!(lab.repair_cost() > 0.0);
+        assert_eq!(lab.demolition_refund(), 120_000.0 * DEMOLITION_REFUND * 0.5);
+    }
+
+    #[test]
+    fn test_building_kind_from_tool() {
+        assert_eq!(BuildingKind::from_tool(Tool::HeliumMine), Some(BuildingKind::HeliumMine));
+        assert_eq!(BuildingKind::from_tool(Tool::PowerCable), None);
+        assert_eq!(BuildingKind::from_tool(Tool::None), None);
+    }
+
+    #[test]
+    fn test_route_cable_straight() {
+        let map = TileMap::new(5, 5);
+        let path = route_cable((0, 2), (4, 2), &map).unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!(path.first(), Some(&(0, 2)));
+        assert_eq!(path.last(), Some(&(4, 2)));
+    }
+
+    #[test]
+    fn test_route_cable_avoids_slopes() {
+        let mut map = TileMap::new(3, 3);
+        // A steep ridge across the middle row, except for a level gap at x = 2
+        set(&mut map, (0, 1), TerrainType::Rough, 0.55);
+        set(&mut map, (1, 1), TerrainType::Rough, 0.55);
+        let path = route_cable((0, 0), (0, 2), &map).unwrap();
+        assert!(path.contains(&(2, 1)));
+
+        // Close the gap and make the ridge impassable
+        set(&mut map, (2, 1), TerrainType::Mountain, 0.0);
+        set(&mut map, (0, 1), TerrainType::Flat, 0.9);
+        set(&mut map, (1, 1), TerrainType::Flat, 0.9);
+        assert_eq!(route_cable((0, 0), (0, 2), &map), None);
+    }
+}
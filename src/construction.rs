@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::{self, Building, BuildingKind};
+use crate::history::History;
+use crate::leveling::{self, Worker};
+use crate::maintenance::SolarFlare;
+use crate::mission::TerrainType;
+use crate::simulation::NewDay;
+use crate::terrain::travel_cost;
+
+/// Power a site needs spare on a neighbouring network to work without deliveries
+pub const SITE_POWER: f32 = 20.0;
+
+/// Tonnes hauled from the lander to each unpowered site per day
+pub const MATERIALS_PER_DAY: f32 = 5.0;
+
+/// A building being put up. It needs a worker to make progress, and either
+/// power from an adjacent grid or materials hauled in; without power, work
+/// can't get ahead of the deliveries.
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct ConstructionSite {
+    pub building: Building,
+    pub work_days: f32,
+    pub progress: f32,
+    pub worker: Option<Worker>,
+    /// Days the assigned bulldozer still has to drive before work starts
+    pub travel: f32,
+    pub delivered: f32,
+    pub powered: bool,
+}
+
+impl ConstructionSite {
+    /// Rough ground and slopes slow the work down just as they slow travel
+    pub fn new(building: Building, terrain: TerrainType, slope: f32) -> Self {
+        let work_days = building.kind.build_days() * travel_cost(terrain, slope).unwrap_or(1.0);
+        Self { building, work_days, progress: 0.0, worker: None, travel: 0.0, delivered: 0.0, powered: false }
+    }
+
+    pub fn kind(&self) -> BuildingKind {
+        self.building.kind
+    }
+
+    /// Share of the materials on site
+    pub fn supplied(&self) -> f32 {
+        (self.delivered / self.kind().materials()).min(1.0)
+    }
+
+    /// How far work can go with the power and materials at hand
+    pub fn work_limit(&self) -> f32 {
+        if self.powered {
+            self.work_days
+        } else {
+            self.work_days * self.supplied()
+        }
+    }
+
+    pub fn done(&self) -> f32 {
+        (self.progress / self.work_days).clamp(0.0, 1.0)
+    }
+}
+
+/// Sent when a site turns into a working building
+#[derive(Event)]
+pub struct ConstructionFinished {
+    pub entity: Entity,
+    pub kind: BuildingKind,
+    pub x: u32,
+    pub y: u32,
+}
+
+pub fn spawn_site(commands: &mut Commands, site: ConstructionSite) -> Entity {
+    let transform = Transform::from_xyz(site.building.x as f32 * 10.0, site.building.y as f32 * 10.0, 1.0);
+    commands.spawn((site, SpatialBundle { transform, ..default() })).id()
+}
+
+/// Haul materials, put in the day's work and hand over finished buildings
+pub fn progress_construction(
+    mut commands: Commands,
+    mut new_day: EventReader<NewDay>,
+    mut sites: Query<(Entity, &mut ConstructionSite)>,
+    mut history: ResMut<History>,
+    mut finished: EventWriter<ConstructionFinished>,
+    flare: Option<Res<SolarFlare>>,
+) {
+    let days = new_day.read().count() as f32;
+    if days == 0.0 {
+        return;
+    }
+
+    for (entity, mut site) in sites.iter_mut() {
+        if !site.powered {
+            site.delivered = (site.delivered + MATERIALS_PER_DAY * days).min(site.kind().materials());
+        }
+        let Some(worker) = site.worker.filter(|&worker| worker.can_work(flare.is_some())) else {
+            continue;
+        };
+        let working = leveling::after_travel(&mut site.travel, days);
+        site.progress = (site.progress + worker.rate() * working).min(site.work_limit());
+        if site.progress < site.work_days {
+            continue;
+        }
+
+        let (kind, x, y) = (site.kind(), site.building.x, site.building.y);
+        let building = buildings::spawn_building(&mut commands, site.building.clone());
+        history.remap(entity, building);
+        commands.entity(entity).despawn();
+        info!("{} finished at ({}, {})", kind.name(), x, y);
+        finished.send(ConstructionFinished { entity: building, kind, x, y });
+    }
+}
+
+pub fn draw_sites(mut gizmos: Gizmos, sites: Query<&ConstructionSite>) {
+    for site in sites.iter() {
+        let center = Vec2::new(site.building.x as f32 * 10.0, site.building.y as f32 * 10.0);
+        let color = site.kind().color().with_a(0.5);
+        gizmos.rect_2d(center, 0.0, Vec2::new(7.0, 7.0), color);
+        // Work done along the bottom, materials on site along the top
+        gizmos.line_2d(
+            center + Vec2::new(-4.0, -4.5),
+            center + Vec2::new(-4.0 + 8.0 * site.done(), -4.5),
+            Color::YELLOW,
+        );
+        if !site.powered {
+            gizmos.line_2d(
+                center + Vec2::new(-4.0, 4.5),
+                center + Vec2::new(-4.0 + 8.0 * site.supplied(), 4.5),
+                Color::CYAN,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .add_event::<ConstructionFinished>()
+            .init_resource::<History>()
+            .add_systems(Update, progress_construction);
+        app
+    }
+
+    fn site(kind: BuildingKind) -> ConstructionSite {
+        ConstructionSite::new(Building::new(kind, 1, 1, 0.0), TerrainType::Flat, 0.0)
+    }
+
+    fn days(app: &mut App, count: u32) {
+        for _ in 0..count {
+            app.world.send_event(NewDay);
+            app.update();
+        }
+    }
+
+    #[test]
+    fn test_build_time_depends_on_ground() {
+        let flat = site(BuildingKind::OxygenPlant);
+        let rough = ConstructionSite::new(flat.building.clone(), TerrainType::Rough, 0.2);
+        assert_eq!(flat.work_days, BuildingKind::OxygenPlant.build_days());
+        assert!(rough.work_days > flat.work_days);
+    }
+
+    #[test]
+    fn test_powered_site_finishes() {
+        let mut app = app();
+        let mut solar = site(BuildingKind::SolarArray);
+        solar.powered = true;
+        solar.worker = Some(Worker::Bulldozer);
+        let entity = app.world.spawn(solar).id();
+
+        days(&mut app, 3);
+        assert!(app.world.get_entity(entity).is_none());
+        let built: Vec<_> = app.world.query::<&Building>().iter(&app.world).map(|b| b.kind).collect();
+        assert_eq!(built, vec![BuildingKind::SolarArray]);
+        assert_eq!(app.world.resource::<Events<ConstructionFinished>>().len(), 1);
+    }
+
+    #[test]
+    fn test_unpowered_site_waits_for_materials() {
+        let mut app = app();
+        // 4 work days but 20 t of materials: deliveries set the pace
+        let mut module = site(BuildingKind::LivingModule);
+        module.worker = Some(Worker::Bulldozer);
+        let entity = app.world.spawn(module).id();
+
+        days(&mut app, 2);
+        let site = app.world.get::<ConstructionSite>(entity).unwrap();
+        assert_eq!(site.delivered, 10.0);
+        assert_eq!(site.progress, 2.0);
+        days(&mut app, 2);
+        assert!(app.world.get_entity(entity).is_none());
+    }
+
+    #[test]
+    fn test_site_needs_a_worker() {
+        let mut app = app();
+        let mut solar = site(BuildingKind::SolarArray);
+        solar.powered = true;
+        let entity = app.world.spawn(solar).id();
+
+        days(&mut app, 5);
+        assert_eq!(app.world.get::<ConstructionSite>(entity).unwrap().progress, 0.0);
+    }
+}
@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::{Building, PowerCable};
+use crate::construction::ConstructionSite;
+use crate::finance::{Account, Treasury};
+use crate::logistics::Shipping;
+use crate::maintenance::{SolarFlare, FLARE_DAYS};
+use crate::map::TileMap;
+use crate::mission::{Mission, TerrainType};
+use crate::simulation::{GameClock, GameRng, NewDay};
+
+/// Metres a meteor digs out at the point of impact
+const METEOR_DEPTH: f32 = 8.0;
+
+/// Health a building takes at the point of impact, less further out
+const METEOR_DAMAGE: f32 = 80.0;
+
+/// Daily chance of each kind of random event, set per mission
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EventRates {
+    pub meteor: f32,
+    pub solar_flare: f32,
+    pub shipment_delay: f32,
+    pub funding_bonus: f32,
+}
+
+impl Default for EventRates {
+    fn default() -> Self {
+        Self { meteor: 0.01, solar_flare: 0.02, shipment_delay: 0.03, funding_bonus: 0.01 }
+    }
+}
+
+/// A disaster or stroke of luck, sent when it happens
+#[derive(Event, Clone, Debug, PartialEq)]
+pub enum RandomEvent {
+    MeteorStrike { x: u32, y: u32, radius: u32 },
+    SolarFlare { days: u32 },
+    ShipmentDelay { days: u32 },
+    FundingBonus { amount: f32 },
+}
+
+impl RandomEvent {
+    pub fn description(&self) -> String {
+        match self {
+            RandomEvent::MeteorStrike { x, y, .. } => format!("Meteor strike at ({}, {})", x, y),
+            RandomEvent::SolarFlare { days } => format!("Solar flare: crew sheltering for {} days", days),
+            RandomEvent::ShipmentDelay { days } => format!("Shuttle schedule slips by {} days", days),
+            RandomEvent::FundingBonus { amount } => format!("Earth funding bonus of ${:.0}", amount),
+        }
+    }
+
+    pub fn is_disaster(&self) -> bool {
+        !matches!(self, RandomEvent::FundingBonus { .. })
+    }
+}
+
+/// Every random event so far, with the day it happened
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    pub entries: Vec<(u32, String)>,
+}
+
+/// Roll the dice for one day's events. A flare can't start while one is
+/// already underway.
+pub fn roll_events(rates: &EventRates, rng: &mut impl Rng, map: &TileMap, flaring: bool) -> Vec<RandomEvent> {
+    let mut events = Vec::new();
+    if rng.gen::<f32>() < rates.meteor {
+        let (x, y) = (rng.gen_range(0..map.width), rng.gen_range(0..map.height));
+        events.push(RandomEvent::MeteorStrike { x, y, radius: rng.gen_range(1..=2) });
+    }
+    if rng.gen::<f32>() < rates.solar_flare && !flaring {
+        events.push(RandomEvent::SolarFlare { days: FLARE_DAYS });
+    }
+    if rng.gen::<f32>() < rates.shipment_delay {
+        events.push(RandomEvent::ShipmentDelay { days: rng.gen_range(1..=3) });
+    }
+    if rng.gen::<f32>() < rates.funding_bonus {
+        events.push(RandomEvent::FundingBonus { amount: (rng.gen_range(50.0..150.0f32) / 10.0).round() * 10_000.0 });
+    }
+    events
+}
+
+/// Blast a crater into the map and damage the buildings around it.
+///
+/// Tiles within reach sink by the force of the blast and turn to crater, and
+/// the slopes in and around the crater are worked out again. Buildings lose
+/// health with distance and the one hit dead on breaks down; they are left
+/// standing on the cratered ground. Returns the cratered tiles, where any
+/// cable is cut.
+pub fn meteor_strike<'a>(
+    tile_map: &mut TileMap,
+    buildings: impl Iterator<Item = Mut<'a, Building>>,
+    x: u32,
+    y: u32,
+    radius: u32,
+) -> Vec<(u32, u32)> {
+    let reach = radius as f32 + 1.0;
+    let falloff = |tx: u32, ty: u32| {
+        let distance = ((tx as f32 - x as f32).powi(2) + (ty as f32 - y as f32).powi(2)).sqrt();
+        (1.0 - distance / reach).max(0.0)
+    };
+    let mut struck = Vec::new();
+    for ty in y.saturating_sub(radius)..=y + radius {
+        for tx in x.saturating_sub(radius)..=x + radius {
+            let force = falloff(tx, ty);
+            let Some(mut tile) = tile_map.tile(tx, ty).filter(|_| force > 0.0) else {
+                continue;
+            };
+            tile.terrain = TerrainType::Crater;
+            tile.elevation -= METEOR_DEPTH * force;
+            tile_map.set(&tile);
+            struck.push((tx, ty));
+        }
+    }
+    for &(tx, ty) in &struck {
+        tile_map.update_slopes(tx, ty);
+    }
+    for mut building in buildings {
+        let force = falloff(building.x, building.y);
+        if force > 0.0 {
+            building.health = (building.health - METEOR_DAMAGE * force).max(0.0);
+            if (building.x, building.y) == (x, y) {
+                building.broken = true;
+            }
+        }
+    }
+    struck
+}
+
+/// Roll each day's random events, carry them out, log them and let the
+/// rest of the game know. A meteor damages construction sites as it does
+/// buildings, and the damage carries over into the finished building.
+pub fn schedule_events(
+    mut commands: Commands,
+    mut new_day: EventReader<NewDay>,
+    clock: Res<GameClock>,
+    mission: Res<Mission>,
+    mut rng: ResMut<GameRng>,
+    mut tile_map: ResMut<TileMap>,
+    mut treasury: ResMut<Treasury>,
+    mut shipping: ResMut<Shipping>,
+    flare: Option<Res<SolarFlare>>,
+    mut buildings: Query<&mut Building>,
+    mut sites: Query<&mut ConstructionSite>,
+    cables: Query<(Entity, &PowerCable)>,
+    mut log: ResMut<EventLog>,
+    mut events: EventWriter<RandomEvent>,
+) {
+    let mut flaring = flare.is_some();
+    for _ in new_day.read() {
+        for event in roll_events(&mission.event_rates, &mut rng.0, &tile_map, flaring) {
+            match event {
+                RandomEvent::MeteorStrike { x, y, radius } => {
+                    let sites = sites.iter_mut().map(|site| site.map_unchanged(|site| &mut site.building));
+                    let struck = meteor_strike(&mut tile_map, buildings.iter_mut().chain(sites), x, y, radius);
+                    for (entity, cable) in cables.iter() {
+                        if struck.contains(&(cable.x, cable.y)) {
+                            commands.entity(entity).despawn();
+                        }
+                    }
+                }
+                RandomEvent::SolarFlare { days } => {
+                    commands.insert_resource(SolarFlare { days_left: days });
+                    flaring = true;
+                }
+                RandomEvent::ShipmentDelay { days } => shipping.delay(days),
+                RandomEvent::FundingBonus { amount } => treasury.earn(Account::Grants, amount),
+            }
+            let description = event.description();
+            if event.is_disaster() {
+                warn!("{}", description);
+            } else {
+                info!("{}", description);
+            }
+            log.entries.push((clock.day, description));
+            events.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const ALWAYS: EventRates = EventRates { meteor: 1.0, solar_flare: 1.0, shipment_delay: 1.0, funding_bonus: 1.0 };
+    const QUIET: EventRates = EventRates { meteor: 0.0, solar_flare: 0.0, shipment_delay: 0.0, funding_bonus: 0.0 };
+
+    #[test]
+    fn test_same_seed_same_events() {
+        let map = TileMap::new(16, 16);
+        let rates = EventRates { meteor: 0.3, solar_flare: 0.3, shipment_delay: 0.3, funding_bonus: 0.3 };
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..50).flat_map(|_| roll_events(&rates, &mut rng, &map, false)).collect::<Vec<_>>()
+        };
+        assert_eq!(run(9), run(9));
+        assert!(!run(9).is_empty());
+    }
+
+    #[test]
+    fn test_rates_control_events() {
+        let map = TileMap::new(16, 16);
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(roll_events(&QUIET, &mut rng, &map, false).is_empty());
+        assert_eq!(roll_events(&ALWAYS, &mut rng, &map, false).len(), 4);
+        // No second flare on top of one already underway
+        assert_eq!(roll_events(&ALWAYS, &mut rng, &map, true).len(), 3);
+    }
+
+    #[test]
+    fn test_meteor_strike_damages_tiles_and_buildings() {
+        let mut map = TileMap::new(8, 8);
+        let mut world = World::new();
+        let hit = world.spawn(Building::new(BuildingKind::SolarArray, 4, 4, 0.0)).id();
+        let near = world.spawn(Building::new(BuildingKind::SolarArray, 5, 4, 0.0)).id();
+        let far = world.spawn(Building::new(BuildingKind::SolarArray, 0, 0, 0.0)).id();
+
+        let mut buildings = world.query::<&mut Building>();
+        let struck = meteor_strike(&mut map, buildings.iter_mut(&mut world), 4, 4, 1);
+
+        assert_eq!(struck.len(), 9);
+        assert_eq!(map.terrain(4, 4), Some(TerrainType::Crater));
+        assert_eq!(map.elevation(4, 4), Some(-METEOR_DEPTH));
+        assert_eq!(map.terrain(0, 0), Some(TerrainType::Flat));
+        // Ground just outside the crater slopes down into it
+        assert!(map.tile(6, 4).unwrap().slope > 0.0);
+        assert_eq!(map.tile(0, 0).unwrap().slope, 0.0);
+        let hit = world.get::<Building>(hit).unwrap();
+        assert!(hit.broken && hit.health == 100.0 - METEOR_DAMAGE);
+        let near = world.get::<Building>(near).unwrap();
+        assert!(!near.broken && near.health > hit.health && near.health < 100.0);
+        assert_eq!(world.get::<Building>(far).unwrap().health, 100.0);
+    }
+
+    #[test]
+    fn test_meteor_cuts_cables_and_damages_sites() {
+        // On a single tile every meteor lands at (0, 0)
+        let mission = Mission { event_rates: EventRates { meteor: 1.0, ..QUIET }, ..Mission::load(1).unwrap() };
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .add_event::<RandomEvent>()
+            .insert_resource(mission)
+            .insert_resource(TileMap::new(1, 1))
+            .insert_resource(Treasury::new(0.0))
+            .init_resource::<GameClock>()
+            .init_resource::<GameRng>()
+            .init_resource::<Shipping>()
+            .init_resource::<EventLog>()
+            .add_systems(Update, schedule_events);
+        let cable = app.world.spawn(PowerCable { x: 0, y: 0, cost: 0.0 }).id();
+        let site = ConstructionSite::new(Building::new(BuildingKind::ScienceLab, 0, 0, 0.0), TerrainType::Flat, 0.0);
+        let site = app.world.spawn(site).id();
+
+        app.world.send_event(NewDay);
+        app.update();
+        assert!(app.world.get_entity(cable).is_none());
+        let site = app.world.get::<ConstructionSite>(site).unwrap();
+        assert_eq!(site.building.health, 100.0 - METEOR_DAMAGE);
+    }
+}
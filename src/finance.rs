@@ -0,0 +1,251 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::NewDay;
+
+/// Days covered by one monthly statement
+pub const DAYS_PER_MONTH: u32 = 30;
+
+/// Days a yearly interest rate is spread over
+pub const DAYS_PER_YEAR: u32 = 365;
+
+/// Daily pay for each crew member on the base
+pub const SALARY_PER_CREW: f32 = 150.0;
+
+/// Daily cost of the packed rations flown up for each crew member without
+/// imported food to eat
+pub const RATION_COST: f32 = 20.0;
+
+/// Ledger headings money is booked under
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Account {
+    Construction,
+    Upkeep,
+    Maintenance,
+    Salaries,
+    Shipments,
+    Purchases,
+    Sales,
+    Grants,
+    Refunds,
+    Loans,
+    Interest,
+}
+
+impl Account {
+    pub const ALL: [Account; 11] = [
+        Account::Construction,
+        Account::Upkeep,
+        Account::Maintenance,
+        Account::Salaries,
+        Account::Shipments,
+        Account::Purchases,
+        Account::Sales,
+        Account::Grants,
+        Account::Refunds,
+        Account::Loans,
+        Account::Interest,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Account::Construction => "Construction",
+            Account::Upkeep => "Upkeep",
+            Account::Maintenance => "Maintenance",
+            Account::Salaries => "Salaries",
+            Account::Shipments => "Shipments",
+            Account::Purchases => "Purchases",
+            Account::Sales => "Sales",
+            Account::Grants => "Grants",
+            Account::Refunds => "Refunds",
+            Account::Loans => "Loans",
+            Account::Interest => "Interest",
+        }
+    }
+}
+
+/// One posting; positive amounts are money coming in
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub day: u32,
+    pub account: Account,
+    pub amount: f32,
+}
+
+/// Net amounts per account over one month
+pub struct Statement {
+    pub month: u32,
+    pub lines: Vec<(Account, f32)>,
+}
+
+impl Statement {
+    pub fn income(&self) -> f32 {
+        self.lines.iter().map(|&(_, amount)| amount).filter(|&amount| amount > 0.0).sum()
+    }
+
+    pub fn expenses(&self) -> f32 {
+        -self.lines.iter().map(|&(_, amount)| amount).filter(|&amount| amount < 0.0).sum::<f32>()
+    }
+
+    pub fn net(&self) -> f32 {
+        self.income() - self.expenses()
+    }
+}
+
+/// Funds on hand, with a ledger of every transaction and the balance each
+/// day closed on. All spending goes through `spend` and `earn` so that the
+/// Financial Report adds up.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Treasury {
+    balance: f32,
+    transactions: Vec<Transaction>,
+    closing: Vec<f32>,
+}
+
+impl Treasury {
+    pub fn new(balance: f32) -> Self {
+        Self { balance, transactions: Vec::new(), closing: Vec::new() }
+    }
+
+    pub fn balance(&self) -> f32 {
+        self.balance
+    }
+
+    pub fn can_afford(&self, amount: f32) -> bool {
+        amount <= self.balance
+    }
+
+    pub fn spend(&mut self, account: Account, amount: f32) {
+        self.post(account, -amount);
+    }
+
+    pub fn earn(&mut self, account: Account, amount: f32) {
+        self.post(account, amount);
+    }
+
+    fn post(&mut self, account: Account, amount: f32) {
+        if amount == 0.0 {
+            return;
+        }
+        self.balance += amount;
+        self.transactions.push(Transaction { day: self.day(), account, amount });
+    }
+
+    /// Day that transactions are currently booked to
+    pub fn day(&self) -> u32 {
+        self.closing.len() as u32
+    }
+
+    pub fn close_day(&mut self) {
+        self.closing.push(self.balance);
+    }
+
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    /// Net amount booked to an account over the last `days` days, today included
+    pub fn recent(&self, account: Account, days: u32) -> f32 {
+        let since = (self.day() + 1).saturating_sub(days);
+        self.transactions.iter().filter(|t| t.account == account && t.day >= since).map(|t| t.amount).sum()
+    }
+
+    /// Balance at the end of each finished day
+    pub fn closing_balances(&self) -> &[f32] {
+        &self.closing
+    }
+
+    pub fn statement(&self, month: u32) -> Statement {
+        let days = month * DAYS_PER_MONTH..(month + 1) * DAYS_PER_MONTH;
+        let lines = Account::ALL
+            .iter()
+            .map(|&account| {
+                let total = self
+                    .transactions
+                    .iter()
+                    .filter(|t| t.account == account && days.contains(&t.day))
+                    .map(|t| t.amount)
+                    .sum();
+                (account, total)
+            })
+            .filter(|&(_, total)| total != 0.0)
+            .collect();
+        Statement { month, lines }
+    }
+
+    /// Average daily change in the balance over the last month
+    pub fn daily_trend(&self) -> f32 {
+        let recent = &self.closing[self.closing.len().saturating_sub(DAYS_PER_MONTH as usize)..];
+        match recent {
+            [] | [_] => 0.0,
+            [first, .., last] => (last - first) / (recent.len() - 1) as f32,
+        }
+    }
+
+    /// Balance expected after `days` more days if the recent trend holds
+    pub fn projection(&self, days: u32) -> f32 {
+        self.balance + self.daily_trend() * days as f32
+    }
+}
+
+/// Close each day's books once all of its spending is in
+pub fn close_books(mut new_day: EventReader<NewDay>, mut treasury: ResMut<Treasury>) {
+    for _ in new_day.read() {
+        treasury.close_day();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_records_transactions() {
+        let mut treasury = Treasury::new(1000.0);
+        treasury.spend(Account::Construction, 300.0);
+        treasury.close_day();
+        treasury.earn(Account::Sales, 50.0);
+
+        assert_eq!(treasury.balance(), 750.0);
+        assert!(treasury.can_afford(750.0) && !treasury.can_afford(751.0));
+        assert_eq!(
+            treasury.transactions(),
+            &[
+                Transaction { day: 0, account: Account::Construction, amount: -300.0 },
+                Transaction { day: 1, account: Account::Sales, amount: 50.0 },
+            ]
+        );
+        assert_eq!(treasury.closing_balances(), &[700.0]);
+        assert_eq!(treasury.recent(Account::Construction, 1), 0.0);
+        assert_eq!(treasury.recent(Account::Construction, 2), -300.0);
+    }
+
+    #[test]
+    fn test_monthly_statement() {
+        let mut treasury = Treasury::new(0.0);
+        treasury.earn(Account::Grants, 500.0);
+        treasury.spend(Account::Upkeep, 100.0);
+        treasury.spend(Account::Upkeep, 50.0);
+        for _ in 0..DAYS_PER_MONTH {
+            treasury.close_day();
+        }
+        treasury.spend(Account::Salaries, 75.0);
+
+        let first = treasury.statement(0);
+        assert_eq!(first.lines, vec![(Account::Upkeep, -150.0), (Account::Grants, 500.0)]);
+        assert_eq!((first.income(), first.expenses(), first.net()), (500.0, 150.0, 350.0));
+        assert_eq!(treasury.statement(1).net(), -75.0);
+    }
+
+    #[test]
+    fn test_projection_follows_the_trend() {
+        let mut treasury = Treasury::new(1000.0);
+        assert_eq!(treasury.projection(30), 1000.0);
+        for _ in 0..5 {
+            treasury.spend(Account::Upkeep, 10.0);
+            treasury.close_day();
+        }
+        assert_eq!(treasury.daily_trend(), -10.0);
+        assert_eq!(treasury.projection(30), 950.0 - 300.0);
+    }
+}
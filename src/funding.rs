@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::{Building, BuildingStatus};
+use crate::finance::{Account, Treasury, DAYS_PER_MONTH, DAYS_PER_YEAR};
+use crate::mission::{BaseProgress, Milestone, Mission, MissionFailed};
+use crate::simulation::{NewDay, Research, Workforce};
+
+/// Days a loan is repaid over
+pub const LOAN_TERM_DAYS: u32 = 360;
+
+/// Months of sales the bank will lend against
+pub const REVENUE_MULTIPLE: f32 = 6.0;
+
+/// Money from a sponsor once the base reaches a milestone
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grant {
+    pub sponsor: String,
+    pub milestone: Milestone,
+    pub amount: f32,
+}
+
+impl Grant {
+    pub fn new(sponsor: &str, milestone: Milestone, amount: f32) -> Self {
+        Self { sponsor: sponsor.to_string(), milestone, amount }
+    }
+}
+
+/// A bank loan, paid back in equal shares of the principal each day with
+/// interest on what is still owed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Loan {
+    pub principal: f32,
+    /// Yearly rate
+    pub rate: f32,
+    pub days_left: u32,
+}
+
+impl Loan {
+    pub fn daily_interest(&self) -> f32 {
+        self.principal * self.rate / DAYS_PER_YEAR as f32
+    }
+
+    pub fn daily_repayment(&self) -> f32 {
+        self.principal / self.days_left.max(1) as f32
+    }
+}
+
+/// Loans taken out, grants paid and how long funds have been negative
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Funding {
+    pub loans: Vec<Loan>,
+    awarded: Vec<usize>,
+    pub days_in_red: u32,
+}
+
+impl Funding {
+    pub fn debt(&self) -> f32 {
+        self.loans.iter().map(|loan| loan.principal).sum()
+    }
+
+    /// How much more the bank will lend: the mission's credit line plus
+    /// several months of recent sales, less what is already owed
+    pub fn credit_available(&self, mission: &Mission, treasury: &Treasury) -> f32 {
+        let revenue = treasury.recent(Account::Sales, DAYS_PER_MONTH);
+        (mission.credit_line + revenue * REVENUE_MULTIPLE - self.debt()).max(0.0)
+    }
+
+    pub fn borrow(&mut self, treasury: &mut Treasury, amount: f32, rate: f32) {
+        treasury.earn(Account::Loans, amount);
+        self.loans.push(Loan { principal: amount, rate, days_left: LOAN_TERM_DAYS });
+    }
+
+    /// Pay a loan off early
+    pub fn repay(&mut self, treasury: &mut Treasury, index: usize) {
+        let loan = self.loans.remove(index);
+        treasury.spend(Account::Loans, loan.principal);
+    }
+
+    pub fn awarded(&self, grant: usize) -> bool {
+        self.awarded.contains(&grant)
+    }
+}
+
+/// Pay each loan's interest and share of the principal
+pub fn service_loans(mut new_day: EventReader<NewDay>, mut funding: ResMut<Funding>, mut treasury: ResMut<Treasury>) {
+    for _ in new_day.read() {
+        for loan in funding.loans.iter_mut() {
+            let repayment = loan.daily_repayment();
+            treasury.spend(Account::Interest, loan.daily_interest());
+            treasury.spend(Account::Loans, repayment);
+            loan.principal -= repayment;
+            loan.days_left -= 1;
+        }
+        funding.loans.retain(|loan| loan.days_left > 0);
+    }
+}
+
+/// Pay out grants for milestones the base has reached
+pub fn award_grants(
+    mut new_day: EventReader<NewDay>,
+    mission: Res<Mission>,
+    mut funding: ResMut<Funding>,
+    mut treasury: ResMut<Treasury>,
+    research: Res<Research>,
+    workforce: Res<Workforce>,
+    buildings: Query<(&Building, Option<&BuildingStatus>)>,
+) {
+    if new_day.read().count() == 0 {
+        return;
+    }
+    let progress = BaseProgress::survey(buildings.iter(), workforce.crew, research.points);
+    for (index, grant) in mission.grants.iter().enumerate() {
+        if funding.awarded(index) || !grant.milestone.reached(&progress) {
+            continue;
+        }
+        info!("{} awards a ${:.0} grant: {}", grant.sponsor, grant.amount, grant.milestone.description());
+        treasury.earn(Account::Grants, grant.amount);
+        funding.awarded.push(index);
+    }
+}
+
+/// Lose the mission once funds have stayed negative past the grace period
+pub fn check_bankruptcy(
+    mut commands: Commands,
+    mut new_day: EventReader<NewDay>,
+    mission: Res<Mission>,
+    mut funding: ResMut<Funding>,
+    treasury: Res<Treasury>,
+) {
+    let days = new_day.read().count() as u32;
+    if days == 0 {
+        return;
+    }
+    if treasury.balance() >= 0.0 {
+        funding.days_in_red = 0;
+        return;
+    }
+    if funding.days_in_red == 0 {
+        warn!("Funds are negative: {} days until bankruptcy", mission.grace_days);
+    }
+    funding.days_in_red += days;
+    if funding.days_in_red > mission.grace_days {
+        warn!("Bankrupt: the mission has been called off");
+        commands.insert_resource(MissionFailed {
+            reason: format!("Funds stayed negative for more than {} days.", mission.grace_days),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+
+    fn app(funds: f32) -> App {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(Mission::load(1).unwrap())
+            .insert_resource(Treasury::new(funds))
+            .insert_resource(Workforce { crew: 8, bulldozers: 1 })
+            .init_resource::<Research>()
+            .init_resource::<Funding>()
+            .add_systems(Update, (service_loans, award_grants, check_bankruptcy));
+        app
+    }
+
+    fn days(app: &mut App, count: u32) {
+        for _ in 0..count {
+            app.world.send_event(NewDay);
+            app.update();
+        }
+    }
+
+    #[test]
+    fn test_loan_is_repaid_with_interest() {
+        let mut app = app(0.0);
+        app.world.resource_scope(|world, mut funding: Mut<Funding>| {
+            funding.borrow(&mut world.resource_mut::<Treasury>(), 36_000.0, 0.1);
+        });
+        days(&mut app, LOAN_TERM_DAYS);
+
+        let treasury = app.world.resource::<Treasury>();
+        assert!(app.world.resource::<Funding>().loans.is_empty());
+        assert!((treasury.recent(Account::Loans, LOAN_TERM_DAYS + 1)).abs() < 0.1);
+        let interest = -treasury.recent(Account::Interest, LOAN_TERM_DAYS + 1);
+        assert!(interest > 0.0 && interest < 36_000.0 * 0.1);
+    }
+
+    #[test]
+    fn test_interest_rate_is_yearly() {
+        let loan = Loan { principal: 36_500.0, rate: 0.1, days_left: LOAN_TERM_DAYS };
+        assert_eq!(loan.daily_interest(), 10.0);
+    }
+
+    #[test]
+    fn test_credit_grows_with_sales() {
+        let mission = Mission::load(1).unwrap();
+        let mut treasury = Treasury::new(0.0);
+        let mut funding = Funding::default();
+        assert_eq!(funding.credit_available(&mission, &treasury), mission.credit_line);
+        treasury.earn(Account::Sales, 10_000.0);
+        funding.borrow(&mut treasury, 20_000.0, mission.interest_rate);
+        assert_eq!(funding.credit_available(&mission, &treasury), mission.credit_line + 60_000.0 - 20_000.0);
+    }
+
+    #[test]
+    fn test_grant_paid_once() {
+        let mut app = app(0.0);
+        app.world.spawn(Building::new(BuildingKind::LivingModule, 0, 0, 0.0));
+        days(&mut app, 2);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 150_000.0);
+    }
+
+    #[test]
+    fn test_bankruptcy_after_grace_period() {
+        let mut app = app(-1.0);
+        let grace = app.world.resource::<Mission>().grace_days;
+        days(&mut app, grace);
+        assert!(app.world.get_resource::<MissionFailed>().is_none());
+        days(&mut app, 1);
+        assert!(app.world.get_resource::<MissionFailed>().is_some());
+    }
+}
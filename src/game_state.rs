@@ -5,6 +5,7 @@ pub enum GameState {
     #[default]
     MainMenu,
     Playing,
+    /// The simulation is stopped and the pause menu is shown
     Paused,
 }
 
@@ -15,6 +16,7 @@ pub struct SelectedTool {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tool {
+    /// Plain pointer: clicking a building inspects it
     None,
     LivingModule,
     OxygenPlant,
@@ -22,8 +24,28 @@ pub enum Tool {
     ScienceLab,
     Telescope,
     HeliumMine,
+    SolarArray,
+    LandingPad,
+    Level,
+    Bulldoze,
 } 
 
+/// Escape pauses a running mission and resumes a paused one
+pub fn toggle_pause(
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        GameState::MainMenu => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,4 +70,27 @@ mod tests {
         assert_ne!(Tool::OxygenPlant, Tool::PowerCable);
         assert_eq!(Tool::ScienceLab, Tool::ScienceLab);
     }
+
+    #[test]
+    fn test_escape_toggles_pause() {
+        let mut app = App::new();
+        app.add_state::<GameState>()
+            .init_resource::<Input<KeyCode>>()
+            .add_systems(Update, toggle_pause);
+        // The new state applies at the start of the next update
+        let press_escape = |app: &mut App| {
+            app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Escape);
+            app.update();
+            app.world.resource_mut::<Input<KeyCode>>().reset(KeyCode::Escape);
+            app.update();
+            *app.world.resource::<State<GameState>>().get()
+        };
+
+        // Nothing to pause on the title screen
+        assert_eq!(press_escape(&mut app), GameState::MainMenu);
+        app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+        assert_eq!(press_escape(&mut app), GameState::Paused);
+        assert_eq!(press_escape(&mut app), GameState::Playing);
+    }
 } 
@@ -0,0 +1,531 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+
+use crate::buildings::{self, Building, PowerCable};
+use crate::construction::{self, ConstructionSite};
+use crate::finance::{Account, Treasury};
+use crate::leveling::LevelingJob;
+use crate::map::TileMap;
+use crate::mission::MapTile;
+use crate::simulation::{GameClock, Workforce};
+
+/// Something on the map that an action put there or took away
+#[derive(Clone)]
+pub enum Structure {
+    Building(Building),
+    Cable(PowerCable),
+    Site(ConstructionSite),
+}
+
+impl Structure {
+    fn spawn(&self, commands: &mut Commands) -> Entity {
+        match self {
+            Structure::Building(building) => buildings::spawn_building(commands, building.clone()),
+            Structure::Cable(cable) => buildings::spawn_cable(commands, cable.clone()),
+            Structure::Site(site) => construction::spawn_site(commands, site.clone()),
+        }
+    }
+
+    /// What it cost to put there
+    fn cost(&self) -> f32 {
+        match self {
+            Structure::Building(building) => building.invested,
+            Structure::Cable(cable) => cable.cost,
+            Structure::Site(site) => site.building.invested,
+        }
+    }
+
+    fn tile(&self) -> (u32, u32) {
+        match self {
+            Structure::Building(building) => (building.x, building.y),
+            Structure::Cable(cable) => (cable.x, cable.y),
+            Structure::Site(site) => (site.building.x, site.building.y),
+        }
+    }
+
+    /// Share of its cost it is still worth
+    fn condition(&self) -> f32 {
+        match self {
+            Structure::Building(building) => building.health / 100.0,
+            Structure::Site(site) => site.building.health / 100.0,
+            Structure::Cable(_) => 1.0,
+        }
+    }
+}
+
+/// A construction action with everything needed to take it back or replay it
+pub enum Action {
+    /// A building or a run of cable, and what it cost. Once undone, it holds
+    /// only what was taken down and the refund paid for it.
+    Build { placed: Vec<(Entity, Structure)>, cost: f32 },
+    /// A leveling job, with the tile as it was before any work was done
+    Level { entity: Entity, job: LevelingJob, tile: MapTile, cost: f32 },
+    /// Structures torn down, with the refund paid, crew sent home and
+    /// salvaged goods handed to other buildings
+    Demolish { removed: Vec<(Entity, Structure)>, refund: f32, crew_lost: u32, moved: Vec<(Entity, f32)> },
+}
+
+impl Action {
+    /// Point at a respawned entity instead of the one it replaced
+    fn remap(&mut self, old: Entity, new: Entity) {
+        let swap = |entity: &mut Entity| {
+            if *entity == old {
+                *entity = new;
+            }
+        };
+        match self {
+            Action::Build { placed, .. } => placed.iter_mut().for_each(|(entity, _)| swap(entity)),
+            Action::Level { entity, .. } => swap(entity),
+            Action::Demolish { removed, moved, .. } => {
+                removed.iter_mut().for_each(|(entity, _)| swap(entity));
+                moved.iter_mut().for_each(|(entity, _)| swap(entity));
+            }
+        }
+    }
+}
+
+struct Entry {
+    day: u32,
+    action: Action,
+}
+
+/// Undo and redo stacks for construction actions
+#[derive(Resource, Default)]
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+impl History {
+    /// Remember a fresh action; anything undone before it can no longer be redone
+    pub fn record(&mut self, day: u32, action: Action) {
+        self.undo.push(Entry { day, action });
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Follow an entity that was replaced, such as a site becoming its building
+    pub fn remap(&mut self, old: Entity, new: Entity) {
+        for entry in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            entry.action.remap(old, new);
+        }
+    }
+}
+
+/// Records actions stamped with the current game day; does nothing before a
+/// mission has started
+#[derive(SystemParam)]
+pub struct Recorder<'w> {
+    clock: Option<Res<'w, GameClock>>,
+    history: Option<ResMut<'w, History>>,
+}
+
+impl Recorder<'_> {
+    pub fn record(&mut self, action: Action) {
+        let day = self.clock.as_ref().map_or(0, |clock| clock.day);
+        if let Some(history) = self.history.as_mut() {
+            history.record(day, action);
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryRequest {
+    Undo,
+    Redo,
+}
+
+/// Ctrl+Z undoes, Ctrl+Y redoes, unless a text field has the keyboard
+pub fn undo_redo_keys(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut requests: EventWriter<HistoryRequest>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Z) {
+        requests.send(HistoryRequest::Undo);
+    } else if keys.just_pressed(KeyCode::Y) {
+        requests.send(HistoryRequest::Redo);
+    }
+}
+
+pub fn apply_history(
+    mut commands: Commands,
+    mut requests: EventReader<HistoryRequest>,
+    mut history: ResMut<History>,
+    clock: Res<GameClock>,
+    mut treasury: ResMut<Treasury>,
+    mut workforce: ResMut<Workforce>,
+    mut tile_map: ResMut<TileMap>,
+    mut buildings: Query<&mut Building>,
+    jobs: Query<&LevelingJob>,
+    sites: Query<&ConstructionSite>,
+    cables: Query<&PowerCable>,
+) {
+    // A tile something else has been put on since, which blocks bringing a structure back
+    let taken = |structures: &[(Entity, Structure)], buildings: &Query<&mut Building>| {
+        structures.iter().map(|(_, structure)| structure.tile()).find(|&tile| {
+            buildings.iter().any(|building| (building.x, building.y) == tile)
+                || sites.iter().any(|site| (site.building.x, site.building.y) == tile)
+                || cables.iter().any(|cable| (cable.x, cable.y) == tile)
+        })
+    };
+
+    for request in requests.read() {
+        match request {
+            HistoryRequest::Undo => {
+                let Some(mut entry) = history.undo.pop() else {
+                    continue;
+                };
+                // Work that is under way can only be taken back on the day it was ordered
+                let not_started = match &entry.action {
+                    Action::Level { entity, .. } => jobs.get(*entity).is_ok_and(|job| job.progress == 0.0),
+                    Action::Build { placed, .. } => placed
+                        .iter()
+                        .all(|(entity, _)| sites.get(*entity).is_ok_and(|site| site.progress == 0.0)),
+                    Action::Demolish { .. } => false,
+                };
+                if entry.day != clock.day && !not_started {
+                    info!("Too late to undo: that was ordered on day {}", entry.day);
+                    history.undo.push(entry);
+                    continue;
+                }
+                if let Action::Demolish { removed, refund, .. } = &entry.action {
+                    if !treasury.can_afford(*refund) {
+                        info!("Not enough funds to undo the demolition: need ${:.0}", refund);
+                        history.undo.push(entry);
+                        continue;
+                    }
+                    if let Some(tile) = taken(removed, &buildings) {
+                        info!("Cannot undo the demolition: {:?} is in use", tile);
+                        history.undo.push(entry);
+                        continue;
+                    }
+                }
+
+                match &mut entry.action {
+                    Action::Build { placed, cost } => {
+                        // Only what is still standing is paid back, less any damage,
+                        // and a redo puts back just that for the same money
+                        let mut refund = 0.0;
+                        placed.retain_mut(|(entity, structure)| {
+                            let Some(mut standing) = commands.get_entity(*entity) else {
+                                return false;
+                            };
+                            if let Ok(building) = buildings.get(*entity) {
+                                *structure = Structure::Building(building.clone());
+                            } else if let Ok(site) = sites.get(*entity) {
+                                *structure = Structure::Site(site.clone());
+                            }
+                            refund += structure.cost() * structure.condition();
+                            standing.despawn();
+                            true
+                        });
+                        *cost = refund;
+                        treasury.earn(Account::Construction, refund);
+                    }
+                    Action::Level { entity, tile, cost, .. } => {
+                        if jobs.contains(*entity) {
+                            commands.entity(*entity).despawn();
+                        } else {
+                            // Already finished today: put the ground back
+                            tile_map.set(tile);
+                            tile_map.update_slopes(tile.x, tile.y);
+                        }
+                        treasury.earn(Account::Construction, *cost);
+                    }
+                    Action::Demolish { removed, refund, crew_lost, moved } => {
+                        let mut respawned = Vec::new();
+                        for (entity, structure) in removed.iter_mut() {
+                            let new = structure.spawn(&mut commands);
+                            respawned.push((*entity, new));
+                            *entity = new;
+                        }
+                        for &(receiver, amount) in moved.iter() {
+                            if let Ok(mut building) = buildings.get_mut(receiver) {
+                                building.stored = (building.stored - amount).max(0.0);
+                            }
+                        }
+                        workforce.crew += *crew_lost;
+                        treasury.spend(Account::Refunds, *refund);
+                        for (old, new) in respawned {
+                            history.remap(old, new);
+                        }
+                    }
+                }
+                history.redo.push(entry);
+            }
+            HistoryRequest::Redo => {
+                let Some(mut entry) = history.redo.pop() else {
+                    continue;
+                };
+                let cost = match &entry.action {
+                    Action::Build { cost, .. } | Action::Level { cost, .. } => *cost,
+                    Action::Demolish { .. } => 0.0,
+                };
+                if !treasury.can_afford(cost) {
+                    info!("Not enough funds to redo: need ${:.0}", cost);
+                    history.redo.push(entry);
+                    continue;
+                }
+                if let Action::Build { placed, .. } = &entry.action {
+                    if let Some(tile) = taken(placed, &buildings) {
+                        info!("Cannot redo: {:?} is in use", tile);
+                        history.redo.push(entry);
+                        continue;
+                    }
+                }
+
+                let mut respawned = Vec::new();
+                match &mut entry.action {
+                    Action::Build { placed, cost } => {
+                        for (entity, structure) in placed.iter_mut() {
+                            let new = structure.spawn(&mut commands);
+                            respawned.push((*entity, new));
+                            *entity = new;
+                        }
+                        treasury.spend(Account::Construction, *cost);
+                    }
+                    Action::Level { entity, job, cost, .. } => {
+                        let new = commands.spawn(job.clone()).id();
+                        respawned.push((*entity, new));
+                        *entity = new;
+                        treasury.spend(Account::Construction, *cost);
+                    }
+                    Action::Demolish { removed, refund, crew_lost, moved } => {
+                        for (entity, _) in removed.iter() {
+                            if let Some(mut entity) = commands.get_entity(*entity) {
+                                entity.despawn();
+                            }
+                        }
+                        for &(receiver, amount) in moved.iter() {
+                            if let Ok(mut building) = buildings.get_mut(receiver) {
+                                building.stored = (building.stored + amount).min(building.storage());
+                            }
+                        }
+                        workforce.crew = workforce.crew.saturating_sub(*crew_lost);
+                        treasury.earn(Account::Refunds, *refund);
+                    }
+                }
+                for (old, new) in respawned {
+                    history.remap(old, new);
+                }
+                // A redo is a fresh order, so today's rules apply to undoing it again
+                entry.day = clock.day;
+                history.undo.push(entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+    use crate::mission::TerrainType;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<HistoryRequest>()
+            .init_resource::<History>()
+            .init_resource::<GameClock>()
+            .insert_resource(Treasury::new(100_000.0))
+            .insert_resource(Workforce { crew: 8, bulldozers: 1 })
+            .insert_resource(TileMap::new(8, 8))
+            .add_systems(Update, apply_history);
+        app
+    }
+
+    fn request(app: &mut App, request: HistoryRequest) {
+        app.world.send_event(request);
+        app.update();
+    }
+
+    fn building_count(app: &mut App) -> usize {
+        app.world.query::<&Building>().iter(&app.world).count()
+    }
+
+    fn place_lab(app: &mut App) {
+        let building = Building::new(BuildingKind::ScienceLab, 2, 2, 60_000.0);
+        let entity = app.world.spawn(building.clone()).id();
+        app.world.resource_mut::<Treasury>().spend(Account::Construction, 60_000.0);
+        app.world.resource_mut::<History>().record(
+            0,
+            Action::Build { placed: vec![(entity, Structure::Building(building))], cost: 60_000.0 },
+        );
+    }
+
+    #[test]
+    fn test_undo_and_redo_placement() {
+        let mut app = app();
+        place_lab(&mut app);
+
+        request(&mut app, HistoryRequest::Undo);
+        assert_eq!(building_count(&mut app), 0);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 100_000.0);
+
+        request(&mut app, HistoryRequest::Redo);
+        assert_eq!(building_count(&mut app), 1);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 40_000.0);
+
+        // The respawned lab is the one a second undo removes
+        request(&mut app, HistoryRequest::Undo);
+        assert_eq!(building_count(&mut app), 0);
+    }
+
+    #[test]
+    fn test_undo_refunds_what_is_left() {
+        let mut damaged = app();
+        place_lab(&mut damaged);
+        let lab = damaged.world.query_filtered::<Entity, With<Building>>().single(&damaged.world);
+        damaged.world.get_mut::<Building>(lab).unwrap().health = 50.0;
+        request(&mut damaged, HistoryRequest::Undo);
+        assert_eq!(damaged.world.resource::<Treasury>().balance(), 40_000.0 + 30_000.0);
+        // A redo brings back the damaged lab for what was refunded
+        request(&mut damaged, HistoryRequest::Redo);
+        assert_eq!(damaged.world.resource::<Treasury>().balance(), 40_000.0);
+        let lab = damaged.world.query::<&Building>().single(&damaged.world);
+        assert_eq!(lab.health, 50.0);
+
+        // Gone before the undo, so nothing comes back
+        let mut destroyed = app();
+        place_lab(&mut destroyed);
+        let lab = destroyed.world.query_filtered::<Entity, With<Building>>().single(&destroyed.world);
+        destroyed.world.despawn(lab);
+        request(&mut destroyed, HistoryRequest::Undo);
+        assert_eq!(destroyed.world.resource::<Treasury>().balance(), 40_000.0);
+        request(&mut destroyed, HistoryRequest::Redo);
+        assert_eq!(building_count(&mut destroyed), 0);
+        assert_eq!(destroyed.world.resource::<Treasury>().balance(), 40_000.0);
+    }
+
+    #[test]
+    fn test_redo_needs_the_tile_free() {
+        let mut app = app();
+        place_lab(&mut app);
+        request(&mut app, HistoryRequest::Undo);
+        app.world.spawn(PowerCable { x: 2, y: 2, cost: 1_000.0 });
+
+        request(&mut app, HistoryRequest::Redo);
+        assert_eq!(building_count(&mut app), 0);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 100_000.0);
+        assert!(app.world.resource::<History>().can_redo());
+    }
+
+    #[test]
+    fn test_undo_only_on_the_same_day() {
+        let mut app = app();
+        place_lab(&mut app);
+        app.world.resource_mut::<GameClock>().day = 1;
+
+        request(&mut app, HistoryRequest::Undo);
+        assert_eq!(building_count(&mut app), 1);
+        assert!(app.world.resource::<History>().can_undo());
+    }
+
+    #[test]
+    fn test_unstarted_leveling_can_be_undone_later() {
+        let mut app = app();
+        let job = LevelingJob::new(1, 1, 3.0);
+        let entity = app.world.spawn(job.clone()).id();
+        let tile = MapTile { x: 1, y: 1, terrain: TerrainType::Rough, elevation: 0.0, slope: 0.1, helium3: 0.0 };
+        app.world.resource_mut::<History>().record(0, Action::Level { entity, job, tile, cost: 10_000.0 });
+        app.world.resource_mut::<GameClock>().day = 3;
+
+        request(&mut app, HistoryRequest::Undo);
+        assert_eq!(app.world.query::<&LevelingJob>().iter(&app.world).count(), 0);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 110_000.0);
+    }
+
+    #[test]
+    fn test_undo_finished_leveling_restores_slopes() {
+        let mut app = app();
+        let job = LevelingJob::new(1, 1, 3.0);
+        let entity = app.world.spawn(job.clone()).id();
+        app.world.despawn(entity);
+        let tile = MapTile { x: 1, y: 1, terrain: TerrainType::Rough, elevation: 4.0, slope: 0.0, helium3: 0.0 };
+        app.world.resource_mut::<History>().record(0, Action::Level { entity, job, tile, cost: 10_000.0 });
+
+        request(&mut app, HistoryRequest::Undo);
+        let tile_map = app.world.resource::<TileMap>();
+        assert_eq!(tile_map.elevation(1, 1), Some(4.0));
+        // The mound is back, so the ground beside it slopes again
+        assert!(tile_map.tile(2, 1).unwrap().slope > 0.0);
+    }
+
+    #[test]
+    fn test_unstarted_site_can_be_undone_later() {
+        let mut app = app();
+        let site = ConstructionSite::new(Building::new(BuildingKind::Telescope, 2, 2, 0.0), TerrainType::Flat, 0.0);
+        let entity = app.world.spawn(site.clone()).id();
+        let placed = vec![(entity, Structure::Site(site))];
+        app.world.resource_mut::<History>().record(0, Action::Build { placed, cost: 0.0 });
+        app.world.resource_mut::<GameClock>().day = 2;
+
+        request(&mut app, HistoryRequest::Undo);
+        assert!(app.world.get_entity(entity).is_none());
+    }
+
+    #[test]
+    fn test_undo_demolition_restores_everything() {
+        let mut app = app();
+        let mut module = Building::new(BuildingKind::LivingModule, 3, 3, 50_000.0);
+        module.stored = 0.0;
+        let receiver = app.world.spawn(Building::new(BuildingKind::OxygenPlant, 5, 5, 0.0)).id();
+        app.world.get_mut::<Building>(receiver).unwrap().stored = 120.0;
+        app.world.resource_mut::<Workforce>().crew = 8;
+        app.world.resource_mut::<Treasury>().earn(Account::Refunds, 25_000.0);
+        app.world.resource_mut::<History>().record(
+            0,
+            Action::Demolish {
+                removed: vec![(Entity::PLACEHOLDER, Structure::Building(module))],
+                refund: 25_000.0,
+                crew_lost: 4,
+                moved: vec![(receiver, 100.0)],
+            },
+        );
+
+        request(&mut app, HistoryRequest::Undo);
+        assert_eq!(building_count(&mut app), 2);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 100_000.0);
+        assert_eq!(app.world.resource::<Workforce>().crew, 12);
+        assert_eq!(app.world.get::<Building>(receiver).unwrap().stored, 20.0);
+
+        request(&mut app, HistoryRequest::Redo);
+        assert_eq!(building_count(&mut app), 1);
+        assert_eq!(app.world.resource::<Workforce>().crew, 8);
+    }
+
+    #[test]
+    fn test_undo_demolition_pays_the_refund_back() {
+        let mut app = app();
+        let module = Building::new(BuildingKind::LivingModule, 3, 3, 50_000.0);
+        app.world.resource_mut::<History>().record(
+            0,
+            Action::Demolish {
+                removed: vec![(Entity::PLACEHOLDER, Structure::Building(module))],
+                refund: 150_000.0,
+                crew_lost: 0,
+                moved: Vec::new(),
+            },
+        );
+
+        request(&mut app, HistoryRequest::Undo);
+        assert_eq!(building_count(&mut app), 0);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 100_000.0);
+        assert!(app.world.resource::<History>().can_undo());
+    }
+}
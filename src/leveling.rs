@@ -0,0 +1,336 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::route_cable;
+use crate::construction::ConstructionSite;
+use crate::maintenance::SolarFlare;
+use crate::map::TileMap;
+use crate::mission::TerrainType;
+use crate::simulation::{NewDay, Workforce};
+use crate::terrain::travel_cost;
+
+/// Tiles of flat, level ground a bulldozer drives in a day
+pub const BULLDOZER_SPEED: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Worker {
+    Crew,
+    Bulldozer,
+}
+
+impl Worker {
+    /// Work days done per calendar day; a bulldozer outpaces a crew with shovels
+    pub fn rate(&self) -> f32 {
+        match self {
+            Worker::Crew => 0.5,
+            Worker::Bulldozer => 1.0,
+        }
+    }
+
+    /// Crew shelter indoors during a solar flare; bulldozers are driven remotely
+    pub fn can_work(&self, flare: bool) -> bool {
+        !(flare && *self == Worker::Crew)
+    }
+}
+
+/// Grading work on a single tile. Nothing happens until a worker is assigned.
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct LevelingJob {
+    pub x: u32,
+    pub y: u32,
+    pub work_days: f32,
+    pub progress: f32,
+    pub worker: Option<Worker>,
+    /// Days the assigned bulldozer still has to drive before work starts
+    pub travel: f32,
+}
+
+impl LevelingJob {
+    pub fn new(x: u32, y: u32, work_days: f32) -> Self {
+        Self { x, y, work_days, progress: 0.0, worker: None, travel: 0.0 }
+    }
+}
+
+/// Where bulldozers set out from: the lander, which came down on the
+/// passable tile nearest the middle of the map
+pub fn landing_site(tile_map: &TileMap) -> Option<(u32, u32)> {
+    let (cx, cy) = ((tile_map.width / 2) as i64, (tile_map.height / 2) as i64);
+    let passable = |&(x, y): &(u32, u32)| tile_map.surface(x, y).and_then(|(t, s)| travel_cost(t, s)).is_some();
+    (0..tile_map.width.max(tile_map.height) as i64).find_map(|ring| {
+        (-ring..=ring)
+            .flat_map(|dy| (-ring..=ring).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| dx.abs().max(dy.abs()) == ring)
+            .map(|(dx, dy)| (cx + dx, cy + dy))
+            .filter(|&(x, y)| x >= 0 && y >= 0)
+            .map(|(x, y)| (x as u32, y as u32))
+            .find(passable)
+    })
+}
+
+/// Days for a bulldozer to drive from the landing site to a tile along the
+/// cheapest route, or `None` if no route reaches it. Slopes and rough ground
+/// slow it down by their `travel_cost`.
+pub fn drive_days(tile_map: &TileMap, to: (u32, u32)) -> Option<f32> {
+    let route = route_cable(landing_site(tile_map)?, to, tile_map)?;
+    let cost: f32 = route
+        .iter()
+        .skip(1)
+        .filter_map(|&(x, y)| tile_map.surface(x, y).and_then(|(terrain, slope)| travel_cost(terrain, slope)))
+        .sum();
+    Some(cost / BULLDOZER_SPEED)
+}
+
+/// Spend `days` on the drive still ahead first, and return what is left for work
+pub fn after_travel(travel: &mut f32, days: f32) -> f32 {
+    let driving = travel.min(days);
+    *travel -= driving;
+    days - driving
+}
+
+/// Price and bulldozer-days to turn a tile into flat ground, or `None` if it
+/// can't be leveled. Crater filling has to be researched first.
+pub fn leveling_job(terrain: TerrainType, slope: f32, crater_filling: bool) -> Option<(f32, f32)> {
+    let (cost, days) = match terrain {
+        TerrainType::Rough => (10_000.0, 3.0),
+        TerrainType::Crater if crater_filling => (40_000.0, 8.0),
+        _ => return None,
+    };
+    Some((cost * (1.0 + slope), days * (1.0 + slope)))
+}
+
+/// Hand idle bulldozers, then idle crew, to leveling jobs and then
+/// construction sites that are waiting. A bulldozer first has to drive out
+/// from the landing site; where it can't get through, crew go on foot.
+pub fn assign_workers(
+    workforce: Res<Workforce>,
+    tile_map: Res<TileMap>,
+    mut jobs: Query<&mut LevelingJob>,
+    mut sites: Query<&mut ConstructionSite>,
+    mut routes: Local<(u64, HashMap<(u32, u32), Option<f32>>)>,
+) {
+    let busy = |worker: Worker| {
+        let leveling = jobs.iter().filter(|job| job.worker == Some(worker)).count();
+        let building = sites.iter().filter(|site| site.worker == Some(worker)).count();
+        (leveling + building) as u32
+    };
+    let mut idle_bulldozers = workforce.bulldozers.saturating_sub(busy(Worker::Bulldozer));
+    let mut idle_crew = workforce.crew.saturating_sub(busy(Worker::Crew));
+
+    // Routes only change with the map, so keep them until it is written to
+    if routes.0 != tile_map.revision() {
+        *routes = (tile_map.revision(), HashMap::new());
+    }
+    let mut pick = |tile: (u32, u32)| {
+        if idle_bulldozers > 0 {
+            if let Some(days) = *routes.1.entry(tile).or_insert_with(|| drive_days(&tile_map, tile)) {
+                idle_bulldozers -= 1;
+                return Some((Worker::Bulldozer, days));
+            }
+        }
+        if idle_crew > 0 {
+            idle_crew -= 1;
+            return Some((Worker::Crew, 0.0));
+        }
+        None
+    };
+
+    for mut job in jobs.iter_mut().filter(|job| job.worker.is_none()) {
+        if let Some((worker, travel)) = pick((job.x, job.y)) {
+            job.worker = Some(worker);
+            job.travel = travel;
+        }
+    }
+    for mut site in sites.iter_mut().filter(|site| site.worker.is_none()) {
+        if let Some((worker, travel)) = pick((site.building.x, site.building.y)) {
+            site.worker = Some(worker);
+            site.travel = travel;
+        }
+    }
+}
+
+pub fn progress_leveling(
+    mut commands: Commands,
+    mut new_day: EventReader<NewDay>,
+    mut jobs: Query<(Entity, &mut LevelingJob)>,
+    mut tile_map: ResMut<TileMap>,
+    flare: Option<Res<SolarFlare>>,
+) {
+    let days = new_day.read().count() as f32;
+    if days == 0.0 {
+        return;
+    }
+
+    for (entity, mut job) in jobs.iter_mut() {
+        let Some(worker) = job.worker.filter(|&worker| worker.can_work(flare.is_some())) else {
+            continue;
+        };
+        let working = after_travel(&mut job.travel, days);
+        job.progress += worker.rate() * working;
+        if job.progress < job.work_days {
+            continue;
+        }
+
+        // Grade the tile to the average height of its surroundings
+        let neighbors: Vec<f32> = tile_map
+            .neighbors8(job.x, job.y)
+            .filter_map(|(x, y)| tile_map.elevation(x, y))
+            .collect();
+        if let Some(mut tile) = tile_map.tile(job.x, job.y) {
+            tile.terrain = TerrainType::Flat;
+            if !neighbors.is_empty() {
+                tile.elevation = neighbors.iter().sum::<f32>() / neighbors.len() as f32;
+            }
+            tile_map.set(&tile);
+            tile_map.update_slopes(job.x, job.y);
+        }
+        info!("Leveling finished at ({}, {})", job.x, job.y);
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn draw_leveling(mut gizmos: Gizmos, jobs: Query<&LevelingJob>) {
+    for job in jobs.iter() {
+        let center = Vec2::new(job.x as f32 * 10.0, job.y as f32 * 10.0);
+        let color = if job.worker.is_some() {
+            Color::rgb(0.8, 0.5, 0.2)
+        } else {
+            Color::rgb(0.4, 0.3, 0.2)
+        };
+        gizmos.rect_2d(center, 0.0, Vec2::new(8.0, 8.0), color);
+        // Progress bar along the bottom edge of the tile
+        let done = (job.progress / job.work_days).clamp(0.0, 1.0);
+        gizmos.line_2d(
+            center + Vec2::new(-4.0, -3.0),
+            center + Vec2::new(-4.0 + 8.0 * done, -3.0),
+            Color::YELLOW,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::MapTile;
+
+    fn tile(x: u32, y: u32, terrain: TerrainType, elevation: f32) -> MapTile {
+        MapTile { x, y, terrain, elevation, slope: 0.4, helium3: 0.0 }
+    }
+
+    #[test]
+    fn test_leveling_job_rules() {
+        assert!(leveling_job(TerrainType::Rough, 0.0, false).is_some());
+        assert_eq!(leveling_job(TerrainType::Crater, 0.0, false), None);
+        assert!(leveling_job(TerrainType::Crater, 0.0, true).is_some());
+        assert_eq!(leveling_job(TerrainType::Flat, 0.0, true), None);
+        assert_eq!(leveling_job(TerrainType::Mountain, 0.0, true), None);
+
+        let (gentle_cost, gentle_days) = leveling_job(TerrainType::Rough, 0.0, false).unwrap();
+        let (steep_cost, steep_days) = leveling_job(TerrainType::Rough, 0.3, false).unwrap();
+        assert!(steep_cost > gentle_cost);
+        assert!(steep_days > gentle_days);
+    }
+
+    #[test]
+    fn test_leveling_needs_a_worker() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(Workforce { crew: 0, bulldozers: 1 })
+            .add_systems(Update, (assign_workers, progress_leveling).chain());
+
+        let mut tile_map = TileMap::new(3, 1);
+        for tile in [
+            tile(1, 0, TerrainType::Rough, 20.0),
+            tile(0, 0, TerrainType::Flat, 10.0),
+            tile(2, 0, TerrainType::Flat, 14.0),
+        ] {
+            tile_map.set(&tile);
+        }
+        app.insert_resource(tile_map);
+        for x in [1, 2] {
+            app.world.spawn(LevelingJob::new(x, 0, 2.0));
+        }
+
+        for _ in 0..2 {
+            app.world.send_event(NewDay);
+            app.update();
+        }
+
+        // Only one bulldozer, so only the first job is done
+        let leveled = app.world.resource::<TileMap>().tile(1, 0).unwrap();
+        assert_eq!((leveled.terrain, leveled.elevation), (TerrainType::Flat, 12.0));
+        // Slopes follow the new elevation, on the tile and beside it
+        let tile_map = app.world.resource::<TileMap>();
+        assert!((leveled.slope - 0.2).abs() < 1e-6);
+        assert!((tile_map.tile(0, 0).unwrap().slope - 0.2).abs() < 1e-6);
+        let remaining: Vec<_> = app.world.query::<&LevelingJob>().iter(&app.world)
+            .map(|job| (job.x, job.worker))
+            .collect();
+        assert_eq!(remaining, vec![(2, None)]);
+
+        // The freed bulldozer moves on to the next job
+        app.world.send_event(NewDay);
+        app.update();
+        let job = app.world.query::<&LevelingJob>().single(&app.world);
+        assert_eq!(job.worker, Some(Worker::Bulldozer));
+    }
+
+    #[test]
+    fn test_bulldozers_drive_out_first() {
+        // The landing site is in the middle; a ridge of mountain blocks the
+        // way west and a steep slope lies to the east
+        let mut tile_map = TileMap::new(21, 3);
+        for y in 0..3 {
+            tile_map.set(&tile(9, y, TerrainType::Mountain, 0.0));
+        }
+        tile_map.set(&MapTile { slope: 0.5, ..tile(15, 1, TerrainType::Flat, 0.0) });
+        for x in [15, 16] {
+            tile_map.set(&MapTile { slope: 0.5, ..tile(x, 0, TerrainType::Flat, 0.0) });
+            tile_map.set(&MapTile { slope: 0.5, ..tile(x, 2, TerrainType::Flat, 0.0) });
+        }
+        assert_eq!(landing_site(&tile_map), Some((10, 1)));
+        assert_eq!(drive_days(&tile_map, (0, 1)), None);
+        let flat = drive_days(&tile_map, (14, 1)).unwrap();
+        assert_eq!(flat, 4.0 / BULLDOZER_SPEED);
+        assert!(drive_days(&tile_map, (20, 1)).unwrap() > flat + 6.0 / BULLDOZER_SPEED);
+
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(Workforce { crew: 1, bulldozers: 1 })
+            .insert_resource(tile_map)
+            .add_systems(Update, (assign_workers, progress_leveling).chain());
+        let far = app.world.spawn(LevelingJob::new(20, 1, 2.0)).id();
+        let blocked = app.world.spawn(LevelingJob::new(0, 1, 2.0)).id();
+        app.update();
+
+        // The bulldozer can't reach the blocked tile, so crew walk there
+        let job = app.world.get::<LevelingJob>(far).unwrap();
+        assert_eq!(job.worker, Some(Worker::Bulldozer));
+        let travel = job.travel;
+        assert!(travel > 0.0);
+        assert_eq!(app.world.get::<LevelingJob>(blocked).unwrap().worker, Some(Worker::Crew));
+
+        app.world.send_event(NewDay);
+        app.update();
+        let job = app.world.get::<LevelingJob>(far).unwrap();
+        assert_eq!(job.travel, 0.0);
+        assert_eq!(job.progress, 1.0 - travel);
+    }
+
+    #[test]
+    fn test_crew_shelter_during_flare() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(TileMap::new(2, 1))
+            .insert_resource(SolarFlare { days_left: 3 })
+            .add_systems(Update, progress_leveling);
+        let job = |x, worker| LevelingJob { worker: Some(worker), ..LevelingJob::new(x, 0, 2.0) };
+        let crew = app.world.spawn(job(0, Worker::Crew)).id();
+        let dozer = app.world.spawn(job(1, Worker::Bulldozer)).id();
+
+        app.world.send_event(NewDay);
+        app.update();
+        assert_eq!(app.world.get::<LevelingJob>(crew).unwrap().progress, 0.0);
+        assert_eq!(app.world.get::<LevelingJob>(dozer).unwrap().progress, 1.0);
+    }
+}
@@ -0,0 +1,27 @@
+// Bevy systems routinely take many parameters and nested query filters
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+pub mod ui;
+pub mod buildings;
+pub mod construction;
+pub mod events;
+pub mod finance;
+pub mod funding;
+pub mod game_state;
+pub mod history;
+pub mod leveling;
+pub mod logistics;
+pub mod maintenance;
+pub mod map;
+pub mod market;
+pub mod mb;
+pub mod mission;
+pub mod news;
+pub mod operations;
+pub mod overlay;
+pub mod save;
+pub mod simulation;
+pub mod terrain;
+pub mod tilemap;
+
+pub const DEFAULT_ZOOM: f32 = 0.5;
@@ -0,0 +1,388 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::{Building, BuildingKind};
+use crate::finance::{Account, Treasury};
+use crate::market::{Commodity, Market, Stockpile};
+use crate::news::{News, NewsKind};
+use crate::simulation::{GameClock, NewDay, Workforce, LANDER_HOUSING};
+
+/// Days between launch windows from Earth
+pub const LAUNCH_INTERVAL: u32 = 7;
+
+/// Days a shuttle spends between Earth and the Moon
+pub const TRANSIT_DAYS: u32 = 3;
+
+/// Days a shuttle stays on the pad before heading home
+pub const TURNAROUND_DAYS: u32 = 1;
+
+/// Tonnes of cargo a shuttle carries each way
+pub const CARGO_CAPACITY: f32 = 40.0;
+
+/// Passenger seats on each shuttle
+pub const SEATS: u32 = 4;
+
+/// Cost of putting a shuttle up, and of each passenger on it
+pub const LAUNCH_COST: f32 = 25_000.0;
+pub const PASSENGER_FARE: f32 = 5_000.0;
+
+/// Tonnes of fuel a shuttle takes on at the base for its flight home. A
+/// shuttle that can refuel here leaves Earth with half the fuel, which takes
+/// `REFUEL_SAVING` off the launch.
+pub const FUEL_PER_SHUTTLE: f32 = 5.0;
+pub const REFUEL_SAVING: f32 = 0.5;
+
+impl Commodity {
+    /// Tonnes per unit, for filling the cargo hold
+    pub fn mass(&self) -> f32 {
+        match self {
+            Commodity::Helium3 => 1e-6,
+            Commodity::ResearchData => 0.0,
+            Commodity::SpareParts => 0.05,
+            Commodity::Water | Commodity::Metals | Commodity::Food | Commodity::Fuel => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Stage {
+    /// On the way from Earth
+    Inbound { arrives: u32 },
+    /// Waiting in orbit because every pad is taken
+    Holding,
+    /// Unloaded on a pad, leaving with exports on the given day
+    Landed { departs: u32 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Flight {
+    pub id: u32,
+    pub stage: Stage,
+    pub cargo: Vec<(Commodity, f32)>,
+    pub crew: u32,
+}
+
+/// Supply shuttles and the goods and crew waiting for them. Imports are
+/// paid for when ordered and ride the next launch with room for them;
+/// exports wait at the pad and are sold when a shuttle takes them away.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Shipping {
+    pub next_launch: u32,
+    pub orders: Vec<(Commodity, f32)>,
+    pub exports: Vec<(Commodity, f32)>,
+    pub crew_wanted: u32,
+    pub flights: Vec<Flight>,
+    launched: u32,
+}
+
+impl Default for Shipping {
+    fn default() -> Self {
+        Self {
+            next_launch: LAUNCH_INTERVAL,
+            orders: Vec::new(),
+            exports: Vec::new(),
+            crew_wanted: 0,
+            flights: Vec::new(),
+            launched: 0,
+        }
+    }
+}
+
+impl Shipping {
+    /// Queue goods for the next launch from Earth; nothing is queued for a
+    /// quantity of zero or less
+    pub fn order(&mut self, commodity: Commodity, quantity: f32) {
+        if quantity > 0.0 {
+            self.orders.push((commodity, quantity));
+        }
+    }
+
+    /// Queue goods for the next shuttle home, as `order` does
+    pub fn export(&mut self, commodity: Commodity, quantity: f32) {
+        if quantity > 0.0 {
+            self.exports.push((commodity, quantity));
+        }
+    }
+
+    /// Units of a commodity ordered or waiting to leave
+    pub fn queued(&self, commodity: Commodity) -> (f32, f32) {
+        let total = |goods: &[(Commodity, f32)]| goods.iter().filter(|(c, _)| *c == commodity).map(|(_, q)| q).sum();
+        (total(&self.orders), total(&self.exports))
+    }
+
+    /// Crew already on their way up
+    pub fn crew_inbound(&self) -> u32 {
+        self.flights.iter().filter(|f| !matches!(f.stage, Stage::Landed { .. })).map(|f| f.crew).sum()
+    }
+
+    /// Push back the next launch and every shuttle still on its way
+    pub fn delay(&mut self, days: u32) {
+        self.next_launch += days;
+        for flight in self.flights.iter_mut() {
+            if let Stage::Inbound { arrives } = &mut flight.stage {
+                *arrives += days;
+            }
+        }
+    }
+
+    pub fn landed(&self) -> usize {
+        self.flights.iter().filter(|f| matches!(f.stage, Stage::Landed { .. })).count()
+    }
+}
+
+/// What the next launch costs with this many passengers
+pub fn launch_cost(crew: u32, refuelled: bool) -> f32 {
+    let saving = if refuelled { REFUEL_SAVING } else { 0.0 };
+    LAUNCH_COST * (1.0 - saving) + crew as f32 * PASSENGER_FARE
+}
+
+/// Move goods from the front of a queue into a hold, splitting the order that
+/// doesn't fit. Empty entries are dropped rather than left to block the queue.
+pub fn load(queue: &mut Vec<(Commodity, f32)>, capacity: f32) -> Vec<(Commodity, f32)> {
+    let mut room = capacity;
+    let mut hold = Vec::new();
+    while let Some((commodity, quantity)) = queue.first_mut() {
+        if *quantity <= 0.0 {
+            queue.remove(0);
+            continue;
+        }
+        let mass = commodity.mass();
+        let fits = if mass > 0.0 { quantity.min(room / mass) } else { *quantity };
+        if fits <= 0.0 {
+            break;
+        }
+        hold.push((*commodity, fits));
+        room -= fits * mass;
+        *quantity -= fits;
+        if *quantity > 0.0 {
+            break;
+        }
+        queue.remove(0);
+    }
+    hold
+}
+
+/// Fly the shuttles. Each day shuttles on the pad leave with exports, those
+/// arriving land if there is a pad free or hold in orbit, and on launch days
+/// a new flight goes up if there is an operating pad and the money for it.
+/// The launch is cheaper when the base has fuel for the flight home, which
+/// is set aside as the shuttle goes up. A missed window pushes everything
+/// back to the next one.
+pub fn run_shuttles(
+    mut new_day: EventReader<NewDay>,
+    clock: Res<GameClock>,
+    mut shipping: ResMut<Shipping>,
+    mut treasury: ResMut<Treasury>,
+    mut stockpile: ResMut<Stockpile>,
+    mut workforce: ResMut<Workforce>,
+    mut market: ResMut<Market>,
+    buildings: Query<&Building>,
+    mut news: EventWriter<News>,
+) {
+    if new_day.read().count() == 0 {
+        return;
+    }
+    let today = clock.day;
+    let pads: Vec<(u32, u32)> = buildings
+        .iter()
+        .filter(|b| b.kind == BuildingKind::LandingPad && b.operating())
+        .map(|b| (b.x, b.y))
+        .collect();
+    let housing = LANDER_HOUSING + buildings.iter().map(|b| b.kind.housing()).sum::<u32>();
+    let shipping = &mut *shipping;
+
+    let mut departing = Vec::new();
+    shipping.flights.retain(|flight| match flight.stage {
+        Stage::Landed { departs } if departs <= today => {
+            departing.push(flight.id);
+            false
+        }
+        _ => true,
+    });
+    for id in departing {
+        let mut sales = 0.0;
+        for (commodity, quantity) in load(&mut shipping.exports, CARGO_CAPACITY) {
+            sales += quantity * market.sell_price(commodity);
+            market.trade(commodity, quantity);
+        }
+        treasury.earn(Account::Sales, sales);
+        info!("Shuttle {} left for Earth", id);
+        if sales > 0.0 {
+            news.send(News::new(NewsKind::Shipment, format!("Shuttle {} exports fetch ${:.0}", id, sales)));
+        }
+    }
+
+    let mut landed = shipping.landed();
+    for flight in shipping.flights.iter_mut() {
+        let due = match flight.stage {
+            Stage::Inbound { arrives } => arrives <= today,
+            Stage::Holding => true,
+            Stage::Landed { .. } => false,
+        };
+        if !due {
+            continue;
+        }
+        let Some(&(x, y)) = pads.get(landed) else {
+            if flight.stage != Stage::Holding {
+                warn!("Shuttle {} is holding in orbit: no landing pad free", flight.id);
+                news.send(News::new(NewsKind::Shipment, format!("Shuttle {} holding in orbit", flight.id)));
+                flight.stage = Stage::Holding;
+            }
+            continue;
+        };
+        for (commodity, quantity) in flight.cargo.drain(..) {
+            stockpile.add(commodity, quantity);
+        }
+        workforce.crew += flight.crew;
+        flight.stage = Stage::Landed { departs: today + TURNAROUND_DAYS };
+        landed += 1;
+        info!("Shuttle {} landed with {} crew", flight.id, flight.crew);
+        let story = format!("Shuttle {} landed with {} crew", flight.id, flight.crew);
+        news.send(News::new(NewsKind::Shipment, story).at(x, y));
+    }
+
+    if today < shipping.next_launch {
+        return;
+    }
+    shipping.next_launch = today + LAUNCH_INTERVAL;
+    if pads.is_empty() {
+        warn!("Launch window missed: no landing pad to receive the shuttle");
+        news.send(News::new(NewsKind::Shipment, "Launch window missed: no landing pad"));
+        return;
+    }
+    let crew = SEATS
+        .min(shipping.crew_wanted)
+        .min(housing.saturating_sub(workforce.crew + shipping.crew_inbound()));
+    let refuelled = stockpile.get(Commodity::Fuel) >= FUEL_PER_SHUTTLE;
+    let cost = launch_cost(crew, refuelled);
+    if !treasury.can_afford(cost) {
+        warn!("Launch window missed: can't pay for the shuttle");
+        news.send(News::new(NewsKind::Shipment, "Launch window missed: no funds for the shuttle"));
+        return;
+    }
+    treasury.spend(Account::Shipments, cost);
+    if refuelled {
+        stockpile.take(Commodity::Fuel, FUEL_PER_SHUTTLE);
+    }
+    shipping.crew_wanted -= crew;
+    shipping.launched += 1;
+    let cargo = load(&mut shipping.orders, CARGO_CAPACITY);
+    let id = shipping.launched;
+    shipping.flights.push(Flight { id, stage: Stage::Inbound { arrives: today + TRANSIT_DAYS }, cargo, crew });
+    info!("Shuttle {} launched with {} crew, arriving in {} days", id, crew, TRANSIT_DAYS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(pads: u32) -> App {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .add_event::<News>()
+            .insert_resource(GameClock::default())
+            .insert_resource(Treasury::new(1_000_000.0))
+            .insert_resource(Workforce { crew: 4, bulldozers: 1 })
+            .init_resource::<Shipping>()
+            .init_resource::<Stockpile>()
+            .init_resource::<Market>()
+            .add_systems(Update, run_shuttles);
+        for x in 0..pads {
+            app.world.spawn(Building::new(BuildingKind::LandingPad, x, 0, 0.0));
+        }
+        app
+    }
+
+    fn day(app: &mut App) {
+        app.world.resource_mut::<GameClock>().day += 1;
+        app.world.send_event(NewDay);
+        app.update();
+    }
+
+    #[test]
+    fn test_load_splits_at_capacity() {
+        let mut queue = vec![(Commodity::Water, 30.0), (Commodity::Food, 30.0), (Commodity::Fuel, 5.0)];
+        let hold = load(&mut queue, CARGO_CAPACITY);
+        assert_eq!(hold, vec![(Commodity::Water, 30.0), (Commodity::Food, 10.0)]);
+        assert_eq!(queue, vec![(Commodity::Food, 20.0), (Commodity::Fuel, 5.0)]);
+
+        // An empty entry at the front doesn't hold up what is behind it
+        let mut queue = vec![(Commodity::Water, 0.0), (Commodity::Metals, 2.0)];
+        assert_eq!(load(&mut queue, CARGO_CAPACITY), vec![(Commodity::Metals, 2.0)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_empty_orders_are_ignored() {
+        let mut shipping = Shipping::default();
+        shipping.order(Commodity::Food, 0.0);
+        shipping.export(Commodity::Water, -1.0);
+        assert!(shipping.orders.is_empty() && shipping.exports.is_empty());
+    }
+
+    #[test]
+    fn test_base_fuel_cheapens_launches() {
+        let mut app = app(1);
+        app.world.resource_mut::<Stockpile>().add(Commodity::Fuel, FUEL_PER_SHUTTLE + 1.0);
+        for _ in 0..LAUNCH_INTERVAL {
+            day(&mut app);
+        }
+        let balance = app.world.resource::<Treasury>().balance();
+        assert_eq!(balance, 1_000_000.0 - LAUNCH_COST * (1.0 - REFUEL_SAVING));
+        assert_eq!(app.world.resource::<Stockpile>().get(Commodity::Fuel), 1.0);
+    }
+
+    #[test]
+    fn test_shuttle_round_trip() {
+        let mut app = app(1);
+        {
+            let mut shipping = app.world.resource_mut::<Shipping>();
+            shipping.order(Commodity::Food, 10.0);
+            shipping.export(Commodity::Water, 5.0);
+            shipping.crew_wanted = 2;
+        }
+        for _ in 0..LAUNCH_INTERVAL {
+            day(&mut app);
+        }
+        let treasury = app.world.resource::<Treasury>().balance();
+        assert_eq!(treasury, 1_000_000.0 - LAUNCH_COST - 2.0 * PASSENGER_FARE);
+
+        for _ in 0..TRANSIT_DAYS {
+            day(&mut app);
+        }
+        assert_eq!(app.world.resource::<Stockpile>().get(Commodity::Food), 10.0);
+        assert_eq!(app.world.resource::<Workforce>().crew, 6);
+
+        for _ in 0..TURNAROUND_DAYS {
+            day(&mut app);
+        }
+        let shipping = app.world.resource::<Shipping>();
+        assert!(shipping.flights.is_empty() && shipping.exports.is_empty());
+        assert!(app.world.resource::<Treasury>().balance() > treasury);
+    }
+
+    #[test]
+    fn test_no_pad_misses_the_window() {
+        let mut app = app(0);
+        app.world.resource_mut::<Shipping>().order(Commodity::Fuel, 1.0);
+        for _ in 0..LAUNCH_INTERVAL {
+            day(&mut app);
+        }
+        let shipping = app.world.resource::<Shipping>();
+        assert!(shipping.flights.is_empty());
+        assert_eq!(shipping.orders.len(), 1);
+        assert_eq!(shipping.next_launch, 2 * LAUNCH_INTERVAL);
+    }
+
+    #[test]
+    fn test_full_pad_holds_arrivals() {
+        let mut app = app(1);
+        app.world.resource_mut::<Shipping>().flights = vec![
+            Flight { id: 1, stage: Stage::Landed { departs: 5 }, cargo: Vec::new(), crew: 0 },
+            Flight { id: 2, stage: Stage::Inbound { arrives: 1 }, cargo: vec![(Commodity::Food, 3.0)], crew: 0 },
+        ];
+        day(&mut app);
+        assert_eq!(app.world.resource::<Shipping>().flights[1].stage, Stage::Holding);
+        assert_eq!(app.world.resource::<Stockpile>().get(Commodity::Food), 0.0);
+    }
+}
@@ -1,17 +1,19 @@
+// Bevy systems routinely take many parameters and nested query filters
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
 use bevy::input::mouse::{MouseWheel, MouseScrollUnit};
-use crate::game_state::GameState;
-
-mod ui;
-mod game_state;
-mod mission;
+use moonbase_online::game_state::{self, GameState};
+use moonbase_online::{
+    buildings, construction, events, finance, funding, history, leveling, logistics, maintenance, market, mission,
+    news, operations, overlay, save, simulation, tilemap, ui, DEFAULT_ZOOM,
+};
 
 const CAMERA_SPEED: f32 = 500.0;
 const ZOOM_SPEED: f32 = 0.5;
 const MIN_ZOOM: f32 = 0.1;
 const MAX_ZOOM: f32 = 5.0;
-const DEFAULT_ZOOM: f32 = 0.5;
 
 fn main() {
     App::new()
@@ -25,21 +27,90 @@ fn main() {
         .add_plugins(EguiPlugin)
         .add_state::<GameState>()
         .init_resource::<ui::NewGameDialog>()
+        .init_resource::<mission::MapDisplay>()
+        .init_resource::<ui::InspectedBuilding>()
+        .init_resource::<ui::FinancialReport>()
+        .init_resource::<ui::TradeCenter>()
+        .init_resource::<ui::EventLogWindow>()
+        .init_resource::<ui::MissionControl>()
+        .init_resource::<ui::MapViewWindow>()
+        .init_resource::<ui::LoadDialog>()
+        .init_resource::<ui::SettingsWindow>()
+        .init_resource::<ui::CreditsWindow>()
+        .init_resource::<overlay::Coverage>()
+        .add_event::<simulation::NewDay>()
+        .add_event::<buildings::Demolish>()
+        .add_event::<construction::ConstructionFinished>()
+        .add_event::<events::RandomEvent>()
+        .add_event::<news::News>()
+        .add_event::<history::HistoryRequest>()
+        .add_event::<save::SaveRequest>()
         .add_systems(Startup, setup)
         .add_systems(Update, (
-            ui::top_menu_bar,
-            ui::side_toolbar,
-            ui::handle_tool_selection,
+            ui::main_menu.run_if(in_state(GameState::MainMenu)),
+            ui::top_menu_bar.run_if(not(in_state(GameState::MainMenu))),
+            ui::side_toolbar.run_if(not(in_state(GameState::MainMenu))),
+            ui::handle_tool_selection.run_if(in_state(GameState::Playing)),
+            ui::building_inspector,
+            ui::notices,
+            ui::event_log,
+            ui::news_ticker,
+            ui::mission_control,
+            ui::map_view,
+            ui::financial_report,
+            ui::trade_center,
+            ui::mission_failed,
             ui::new_game_dialog,
+            ui::load_dialog,
+            ui::settings,
+            ui::credits,
+            history::undo_redo_keys.run_if(in_state(GameState::Playing)),
+            ui::pause_menu.run_if(in_state(GameState::Paused)),
+            game_state::toggle_pause,
         ))
-        .add_systems(OnExit(GameState::MainMenu), cleanup_map)
-        .add_systems(OnEnter(GameState::Playing), mission::setup_mission)
+        .add_systems(Update, save::write_saves.run_if(not(in_state(GameState::MainMenu))))
+        .add_systems(OnEnter(GameState::MainMenu), mission::teardown_mission)
+        .add_systems(OnTransition { from: GameState::MainMenu, to: GameState::Playing }, mission::setup_mission)
         .add_systems(Update, (
-            mission::draw_map,
+            overlay::update_coverage,
+            tilemap::update_terrain_chunks,
+            tilemap::draw_grid,
+            buildings::draw_structures,
+            construction::draw_sites,
             camera_movement,
             camera_zoom,
             mission::handle_tile_hover,
-        ).chain().run_if(in_state(GameState::Playing)))
+        ).chain().run_if(not(in_state(GameState::MainMenu))))
+        .add_systems(Update, (
+            (
+                simulation::advance_clock,
+                leveling::assign_workers,
+                operations::update_building_status,
+                simulation::accumulate_research,
+                operations::run_production,
+                operations::breathe,
+                operations::pay_upkeep,
+                market::update_market,
+                logistics::run_shuttles,
+                maintenance::solar_flares,
+                events::schedule_events,
+                maintenance::wear_and_tear,
+            ).chain(),
+            (
+                operations::demolish,
+                history::apply_history,
+                leveling::progress_leveling,
+                construction::progress_construction,
+                leveling::draw_leveling,
+                funding::service_loans,
+                funding::award_grants,
+                funding::check_bankruptcy,
+                mission::track_objectives,
+                (news::report_construction, news::report_random_events),
+                news::collect_news,
+                finance::close_books,
+            ).chain(),
+        ).chain().run_if(in_state(GameState::Playing)).run_if(not(resource_exists::<mission::MissionFailed>())))
         .run();
 }
 
@@ -114,16 +185,6 @@ fn camera_zoom(
     }
 }
 
-fn cleanup_map(
-    mut commands: Commands,
-    map_tiles: Query<Entity, With<mission::MapTile>>,
-) {
-    // Remove all existing map tiles
-    for entity in map_tiles.iter() {
-        commands.entity(entity).despawn();
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,6 +243,7 @@ mod tests {
         
         // Setup minimal app with required systems
         app.add_plugins(MinimalPlugins)
+            .add_event::<MouseWheel>()
             .add_systems(Update, camera_zoom);
         
         // Spawn camera with default zoom
@@ -194,16 +256,14 @@ mod tests {
         let window_entity = app.world.spawn_empty().id();
 
         // Test zoom in with multiple scroll events
-        let mut events = Events::<MouseWheel>::default();
         for _ in 0..3 {  // Send multiple scroll events
-            events.send(MouseWheel {
+            app.world.send_event(MouseWheel {
                 unit: MouseScrollUnit::Line,
                 x: 0.0,
                 y: -2.0,  // Scroll down to zoom in
                 window: window_entity,
             });
         }
-        app.world.insert_resource(events);
         app.update();
 
         let transform = app.world.query::<&Transform>().single(&app.world);
@@ -216,16 +276,14 @@ mod tests {
         let mut transform = app.world.query::<&mut Transform>().single_mut(&mut app.world);
         transform.scale = Vec3::splat(DEFAULT_ZOOM);
 
-        let mut events = Events::<MouseWheel>::default();
         for _ in 0..3 {  // Send multiple scroll events
-            events.send(MouseWheel {
+            app.world.send_event(MouseWheel {
                 unit: MouseScrollUnit::Line,
                 x: 0.0,
                 y: 2.0,  // Scroll up to zoom out
                 window: window_entity,
             });
         }
-        app.world.insert_resource(events);
         app.update();
 
         let transform = app.world.query::<&Transform>().single(&app.world);
@@ -238,16 +296,14 @@ mod tests {
         let mut transform = app.world.query::<&mut Transform>().single_mut(&mut app.world);
         transform.scale = Vec3::splat(MIN_ZOOM * 1.1);  // Just above minimum
 
-        let mut events = Events::<MouseWheel>::default();
         for _ in 0..5 {  // Send multiple scroll events
-            events.send(MouseWheel {
+            app.world.send_event(MouseWheel {
                 unit: MouseScrollUnit::Line,
                 x: 0.0,
                 y: -2.0,  // Try to zoom in past minimum
                 window: window_entity,
             });
         }
-        app.world.insert_resource(events);
         app.update();
 
         let transform = app.world.query::<&Transform>().single(&app.world);
@@ -259,16 +315,14 @@ mod tests {
         let mut transform = app.world.query::<&mut Transform>().single_mut(&mut app.world);
         transform.scale = Vec3::splat(MAX_ZOOM * 0.9);  // Just below maximum
 
-        let mut events = Events::<MouseWheel>::default();
         for _ in 0..5 {  // Send multiple scroll events
-            events.send(MouseWheel {
+            app.world.send_event(MouseWheel {
                 unit: MouseScrollUnit::Line,
                 x: 0.0,
                 y: 2.0,  // Try to zoom out past maximum
                 window: window_entity,
             });
         }
-        app.world.insert_resource(events);
         app.update();
 
         let transform = app.world.query::<&Transform>().single(&app.world);
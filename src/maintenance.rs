@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::Building;
+use crate::finance::{Account, Treasury};
+use crate::map::TileMap;
+use crate::market::{Commodity, Stockpile};
+use crate::simulation::{GameRng, NewDay};
+use crate::terrain::travel_cost;
+
+/// Health lost per day by an unmaintained building on flat ground
+pub const BASE_WEAR: f32 = 1.0;
+
+/// Full maintenance slows wear down to this share
+pub const MAINTAINED_WEAR: f32 = 0.25;
+
+/// Daily cost of fully maintaining one building
+pub const MAINTENANCE_COST: f32 = 200.0;
+
+/// Buildings one crew member can look after at full maintenance
+pub const BUILDINGS_PER_MAINTAINER: f32 = 4.0;
+
+/// Below this health a building may break down; at 0 it always does
+pub const BREAKDOWN_HEALTH: f32 = 50.0;
+
+/// Crates of spare parts used up fixing a breakdown on the spot
+pub const PARTS_PER_BREAKDOWN: f32 = 1.0;
+
+/// How long a solar flare lasts
+pub const FLARE_DAYS: u32 = 3;
+
+/// Wear multiplier while a flare is underway
+pub const FLARE_WEAR: f32 = 4.0;
+
+/// How much of full maintenance is paid for, from 0 to 1
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct MaintenanceBudget {
+    pub share: f32,
+}
+
+impl Default for MaintenanceBudget {
+    fn default() -> Self {
+        Self { share: 0.5 }
+    }
+}
+
+impl MaintenanceBudget {
+    pub fn daily_cost(&self, buildings: usize) -> f32 {
+        self.share * MAINTENANCE_COST * buildings as f32
+    }
+
+    /// Crew kept busy on maintenance rounds instead of working shifts
+    pub fn crew(&self, buildings: usize) -> u32 {
+        (self.share * buildings as f32 / BUILDINGS_PER_MAINTAINER).ceil() as u32
+    }
+
+    fn wear_factor(&self) -> f32 {
+        1.0 - (1.0 - MAINTAINED_WEAR) * self.share.clamp(0.0, 1.0)
+    }
+}
+
+/// A solar flare in progress. Structures wear faster and crew shelter
+/// indoors instead of working outside.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct SolarFlare {
+    pub days_left: u32,
+}
+
+/// Health a building loses in a day. Rough and sloped ground wear it down
+/// just as they slow travel.
+pub fn daily_wear(tile_map: &TileMap, building: &Building, budget: &MaintenanceBudget, flare: bool) -> f32 {
+    let ground = tile_map
+        .surface(building.x, building.y)
+        .and_then(|(terrain, slope)| travel_cost(terrain, slope))
+        .unwrap_or(1.0);
+    let flare = if flare { FLARE_WEAR } else { 1.0 };
+    BASE_WEAR * ground * flare * budget.wear_factor()
+}
+
+/// Chance that a building at this health breaks down on a given day
+pub fn breakdown_chance(health: f32) -> f32 {
+    ((BREAKDOWN_HEALTH - health) / BREAKDOWN_HEALTH).clamp(0.0, 1.0)
+}
+
+/// Run solar flares down until they pass
+pub fn solar_flares(mut commands: Commands, mut new_day: EventReader<NewDay>, flare: Option<ResMut<SolarFlare>>) {
+    let days = new_day.read().count() as u32;
+    let Some(mut flare) = flare.filter(|_| days > 0) else {
+        return;
+    };
+    flare.days_left = flare.days_left.saturating_sub(days);
+    if flare.days_left == 0 {
+        info!("The solar flare has passed");
+        commands.remove_resource::<SolarFlare>();
+    }
+}
+
+/// Wear buildings down, pay for maintenance and break down worn-out buildings.
+/// While there are spare parts in the stockpile a breakdown is fixed on the
+/// spot instead, though the building stays worn and may fail again.
+pub fn wear_and_tear(
+    mut new_day: EventReader<NewDay>,
+    tile_map: Res<TileMap>,
+    budget: Res<MaintenanceBudget>,
+    flare: Option<Res<SolarFlare>>,
+    mut treasury: ResMut<Treasury>,
+    mut stockpile: ResMut<Stockpile>,
+    mut rng: ResMut<GameRng>,
+    mut buildings: Query<&mut Building>,
+) {
+    let days = new_day.read().count() as f32;
+    if days == 0.0 {
+        return;
+    }
+
+    treasury.spend(Account::Maintenance, budget.daily_cost(buildings.iter().len()) * days);
+    for mut building in buildings.iter_mut() {
+        let wear = daily_wear(&tile_map, &building, &budget, flare.is_some()) * days;
+        building.health = (building.health - wear).max(0.0);
+        if !building.broken && rng.0.gen::<f32>() < breakdown_chance(building.health) {
+            if stockpile.get(Commodity::SpareParts) >= PARTS_PER_BREAKDOWN {
+                stockpile.take(Commodity::SpareParts, PARTS_PER_BREAKDOWN);
+                info!("{} at ({}, {}) fixed with spare parts", building.kind.name(), building.x, building.y);
+                continue;
+            }
+            building.broken = true;
+            warn!("{} at ({}, {}) has broken down", building.kind.name(), building.x, building.y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+    use crate::mission::{MapTile, TerrainType};
+
+    #[test]
+    fn test_wear_rules() {
+        let mut map = TileMap::new(2, 1);
+        map.set(&MapTile { x: 1, y: 0, terrain: TerrainType::Rough, elevation: 0.0, slope: 0.2, helium3: 0.0 });
+        let flat = Building::new(BuildingKind::ScienceLab, 0, 0, 0.0);
+        let rough = Building::new(BuildingKind::ScienceLab, 1, 0, 0.0);
+        let none = MaintenanceBudget { share: 0.0 };
+        let full = MaintenanceBudget { share: 1.0 };
+
+        assert_eq!(daily_wear(&map, &flat, &none, false), BASE_WEAR);
+        assert!(daily_wear(&map, &rough, &none, false) > BASE_WEAR);
+        assert_eq!(daily_wear(&map, &flat, &none, true), BASE_WEAR * FLARE_WEAR);
+        assert_eq!(daily_wear(&map, &flat, &full, false), BASE_WEAR * MAINTAINED_WEAR);
+    }
+
+    #[test]
+    fn test_breakdown_chance() {
+        assert_eq!(breakdown_chance(100.0), 0.0);
+        assert_eq!(breakdown_chance(BREAKDOWN_HEALTH), 0.0);
+        assert_eq!(breakdown_chance(0.0), 1.0);
+    }
+
+    #[test]
+    fn test_budget_costs_money_and_crew() {
+        let budget = MaintenanceBudget { share: 0.5 };
+        assert_eq!(budget.daily_cost(4), 2.0 * MAINTENANCE_COST);
+        assert_eq!(budget.crew(4), 1);
+        assert_eq!(MaintenanceBudget { share: 0.0 }.crew(10), 0);
+    }
+
+    #[test]
+    fn test_worn_out_building_breaks_down() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(TileMap::new(4, 1))
+            .insert_resource(MaintenanceBudget { share: 0.0 })
+            .insert_resource(Treasury::new(0.0))
+            .init_resource::<Stockpile>()
+            .init_resource::<GameRng>()
+            .add_systems(Update, wear_and_tear);
+        let mut worn = Building::new(BuildingKind::OxygenPlant, 0, 0, 0.0);
+        worn.health = 0.5;
+        let worn = app.world.spawn(worn).id();
+        let sound = app.world.spawn(Building::new(BuildingKind::OxygenPlant, 1, 0, 0.0)).id();
+
+        app.world.send_event(NewDay);
+        app.update();
+        assert!(app.world.get::<Building>(worn).unwrap().broken);
+        let sound = app.world.get::<Building>(sound).unwrap();
+        assert!(!sound.broken);
+        assert_eq!(sound.health, 100.0 - BASE_WEAR);
+    }
+
+    #[test]
+    fn test_spare_parts_fix_breakdowns() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(TileMap::new(4, 1))
+            .insert_resource(MaintenanceBudget { share: 0.0 })
+            .insert_resource(Treasury::new(0.0))
+            .init_resource::<Stockpile>()
+            .init_resource::<GameRng>()
+            .add_systems(Update, wear_and_tear);
+        app.world.resource_mut::<Stockpile>().add(Commodity::SpareParts, PARTS_PER_BREAKDOWN);
+        let mut worn = Building::new(BuildingKind::OxygenPlant, 0, 0, 0.0);
+        worn.health = 0.5;
+        let worn = app.world.spawn(worn).id();
+
+        app.world.send_event(NewDay);
+        app.update();
+        assert!(!app.world.get::<Building>(worn).unwrap().broken);
+        assert_eq!(app.world.resource::<Stockpile>().get(Commodity::SpareParts), 0.0);
+
+        // With the crate used up, the next failure sticks
+        app.world.send_event(NewDay);
+        app.update();
+        assert!(app.world.get::<Building>(worn).unwrap().broken);
+    }
+}
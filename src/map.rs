@@ -0,0 +1,346 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::mission::{MapTile, TerrainType};
+use crate::terrain::slope_at;
+
+/// Tiles per side of one storage chunk, which is also one terrain mesh
+pub const CHUNK_SIZE: u32 = 32;
+
+pub fn chunk_of(x: u32, y: u32) -> (u32, u32) {
+    (x / CHUNK_SIZE, y / CHUNK_SIZE)
+}
+
+/// Surface data for one `CHUNK_SIZE` square, stored as flat arrays, with
+/// the lowest and highest elevation in it
+struct Chunk {
+    terrain: Vec<TerrainType>,
+    elevation: Vec<f32>,
+    slope: Vec<f32>,
+    helium3: Vec<f32>,
+    low: f32,
+    high: f32,
+}
+
+impl Chunk {
+    fn new() -> Self {
+        let size = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+        Self {
+            terrain: vec![TerrainType::Flat; size],
+            elevation: vec![0.0; size],
+            slope: vec![0.0; size],
+            helium3: vec![0.0; size],
+            low: 0.0,
+            high: 0.0,
+        }
+    }
+
+    /// Keep `low` and `high` right after one elevation changed. Only when
+    /// the old value was an extreme and the new one lies inside the range
+    /// does the chunk need scanning again.
+    fn set_elevation(&mut self, i: usize, elevation: f32) {
+        let old = std::mem::replace(&mut self.elevation[i], elevation);
+        if elevation <= self.low || elevation >= self.high {
+            self.low = self.low.min(elevation);
+            self.high = self.high.max(elevation);
+        }
+        if (old == self.low && elevation > old) || (old == self.high && elevation < old) {
+            let (low, high) = fold_range(self.elevation.iter().copied());
+            (self.low, self.high) = (low, high);
+        }
+    }
+}
+
+fn fold_range(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    values.fold((f32::MAX, f32::MIN), |(min, max), e| (min.min(e), max.max(e)))
+}
+
+/// The map surface, stored in chunks rather than as one entity per tile so
+/// that very large maps stay cheap to hold and to simulate.
+///
+/// Every write marks its chunk dirty; the renderer drains those marks to
+/// re-mesh only what changed. Tiles written after the map was generated are
+/// also remembered, so a save only needs to store those.
+#[derive(Resource)]
+pub struct TileMap {
+    pub width: u32,
+    pub height: u32,
+    chunks: Vec<Chunk>,
+    dirty: HashSet<(u32, u32)>,
+    edited: HashSet<(u32, u32)>,
+    revision: u64,
+}
+
+impl TileMap {
+    pub fn new(width: u32, height: u32) -> Self {
+        let (chunks_x, chunks_y) = (width.div_ceil(CHUNK_SIZE), height.div_ceil(CHUNK_SIZE));
+        Self {
+            width,
+            height,
+            chunks: (0..chunks_x * chunks_y).map(|_| Chunk::new()).collect(),
+            dirty: HashSet::new(),
+            edited: HashSet::new(),
+            revision: 0,
+        }
+    }
+
+    /// Build a map from row-major layers, as produced by the terrain generator
+    pub fn from_layers(
+        width: u32,
+        height: u32,
+        terrain: &[TerrainType],
+        elevation: &[f32],
+        slope: &[f32],
+        helium3: &[f32],
+    ) -> Self {
+        let mut map = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let i = (y * width + x) as usize;
+                map.set(&MapTile {
+                    x,
+                    y,
+                    terrain: terrain[i],
+                    elevation: elevation[i],
+                    slope: slope[i],
+                    helium3: helium3[i],
+                });
+            }
+        }
+        map.dirty.clear();
+        map.edited.clear();
+        map
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Number of chunks across and down
+    pub fn chunk_count(&self) -> (u32, u32) {
+        (self.width.div_ceil(CHUNK_SIZE), self.height.div_ceil(CHUNK_SIZE))
+    }
+
+    /// Tile under a world position; tiles are 10 units wide and centred on `x * 10`
+    pub fn tile_at(&self, world_pos: Vec2) -> Option<(u32, u32)> {
+        let x = (world_pos.x / 10.0).round();
+        let y = (world_pos.y / 10.0).round();
+        if x < 0.0 || y < 0.0 || !self.contains(x as u32, y as u32) {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    /// Chunk and offset within it
+    fn index(&self, x: u32, y: u32) -> Option<(usize, usize)> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        let (cx, cy) = chunk_of(x, y);
+        let chunk = (cy * self.chunk_count().0 + cx) as usize;
+        let offset = ((y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE) as usize;
+        Some((chunk, offset))
+    }
+
+    /// Overwrite a tile's surface and mark its chunk for re-meshing
+    pub fn set(&mut self, tile: &MapTile) {
+        if let Some((c, i)) = self.index(tile.x, tile.y) {
+            let chunk = &mut self.chunks[c];
+            chunk.terrain[i] = tile.terrain;
+            chunk.set_elevation(i, tile.elevation);
+            chunk.slope[i] = tile.slope;
+            chunk.helium3[i] = tile.helium3;
+            self.dirty.insert(chunk_of(tile.x, tile.y));
+            self.edited.insert((tile.x, tile.y));
+            self.revision += 1;
+        }
+    }
+
+    /// Count of writes so far, for views that redraw when the map changes
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn tile(&self, x: u32, y: u32) -> Option<MapTile> {
+        self.index(x, y).map(|(c, i)| {
+            let chunk = &self.chunks[c];
+            MapTile {
+                x,
+                y,
+                terrain: chunk.terrain[i],
+                elevation: chunk.elevation[i],
+                slope: chunk.slope[i],
+                helium3: chunk.helium3[i],
+            }
+        })
+    }
+
+    pub fn terrain(&self, x: u32, y: u32) -> Option<TerrainType> {
+        self.index(x, y).map(|(c, i)| self.chunks[c].terrain[i])
+    }
+
+    pub fn elevation(&self, x: u32, y: u32) -> Option<f32> {
+        self.index(x, y).map(|(c, i)| self.chunks[c].elevation[i])
+    }
+
+    pub fn helium3(&self, x: u32, y: u32) -> Option<f32> {
+        self.index(x, y).map(|(c, i)| self.chunks[c].helium3[i])
+    }
+
+    /// Lowest and highest elevation on the map, from each chunk's own range
+    pub fn elevation_range(&self) -> (f32, f32) {
+        let low = fold_range(self.chunks.iter().map(|chunk| chunk.low)).0;
+        let high = fold_range(self.chunks.iter().map(|chunk| chunk.high)).1;
+        (low, high)
+    }
+
+    /// Terrain and slope together, the pair most placement rules need
+    pub fn surface(&self, x: u32, y: u32) -> Option<(TerrainType, f32)> {
+        self.index(x, y).map(|(c, i)| (self.chunks[c].terrain[i], self.chunks[c].slope[i]))
+    }
+
+    /// Work the slope out again for a tile and its eight neighbours after its
+    /// elevation has changed
+    pub fn update_slopes(&mut self, x: u32, y: u32) {
+        let around: Vec<(u32, u32)> = std::iter::once((x, y)).chain(self.neighbors8(x, y)).collect();
+        for (x, y) in around {
+            let slope = slope_at(x, y, self.width, self.height, |x, y| self.elevation(x, y).unwrap_or(0.0));
+            if let Some(tile) = self.tile(x, y).filter(|tile| tile.slope != slope) {
+                self.set(&MapTile { slope, ..tile });
+            }
+        }
+    }
+
+    /// Tiles that no longer match the generated map, such as leveled ground
+    /// and meteor craters
+    pub fn edited_tiles(&self) -> Vec<MapTile> {
+        self.edited.iter().filter_map(|&(x, y)| self.tile(x, y)).collect()
+    }
+
+    /// Chunks written to since the last call
+    pub fn take_dirty_chunks(&mut self) -> HashSet<(u32, u32)> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// The four edge-adjacent tiles that lie on the map
+    pub fn neighbors4(&self, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| self.offset(x, y, dx, dy))
+    }
+
+    /// All eight surrounding tiles that lie on the map
+    pub fn neighbors8(&self, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .filter_map(move |(dx, dy)| self.offset(x, y, dx, dy))
+    }
+
+    fn offset(&self, x: u32, y: u32, dx: i32, dy: i32) -> Option<(u32, u32)> {
+        let nx = x.checked_add_signed(dx)?;
+        let ny = y.checked_add_signed(dy)?;
+        self.contains(nx, ny).then_some((nx, ny))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_of() {
+        assert_eq!(chunk_of(0, 0), (0, 0));
+        assert_eq!(chunk_of(CHUNK_SIZE - 1, CHUNK_SIZE), (0, 1));
+        assert_eq!(chunk_of(63, 63), (1, 1));
+    }
+
+    #[test]
+    fn test_lookup_and_bounds() {
+        let mut map = TileMap::new(40, 35);
+        map.set(&MapTile { x: 39, y: 34, terrain: TerrainType::Crater, elevation: -4.0, slope: 0.3, helium3: 12.0 });
+
+        assert_eq!(map.terrain(39, 34), Some(TerrainType::Crater));
+        assert_eq!(map.elevation(39, 34), Some(-4.0));
+        assert_eq!(map.helium3(39, 34), Some(12.0));
+        assert_eq!(map.surface(39, 34), Some((TerrainType::Crater, 0.3)));
+        assert_eq!(map.tile(39, 34).map(|tile| tile.slope), Some(0.3));
+        assert_eq!(map.terrain(0, 0), Some(TerrainType::Flat));
+        assert_eq!(map.terrain(40, 0), None);
+        assert!(map.tile(0, 35).is_none());
+        assert_eq!(map.chunk_count(), (2, 2));
+    }
+
+    #[test]
+    fn test_from_layers() {
+        let terrain = [TerrainType::Flat, TerrainType::Rough, TerrainType::Crater, TerrainType::Mountain];
+        let mut map = TileMap::from_layers(2, 2, &terrain, &[0.0, 1.0, 2.0, 3.0], &[0.0; 4], &[0.0; 4]);
+        assert_eq!(map.terrain(1, 0), Some(TerrainType::Rough));
+        assert_eq!(map.terrain(0, 1), Some(TerrainType::Crater));
+        assert_eq!(map.elevation_range(), (0.0, 3.0));
+        assert!(map.take_dirty_chunks().is_empty());
+        assert!(map.edited_tiles().is_empty());
+
+        let leveled = MapTile { x: 1, y: 1, terrain: TerrainType::Flat, elevation: 1.5, slope: 0.0, helium3: 0.0 };
+        map.set(&leveled);
+        assert_eq!(map.edited_tiles(), vec![leveled]);
+    }
+
+    #[test]
+    fn test_tile_at() {
+        let map = TileMap::new(64, 64);
+        assert_eq!(map.tile_at(Vec2::new(0.0, 0.0)), Some((0, 0)));
+        assert_eq!(map.tile_at(Vec2::new(4.9, 15.1)), Some((0, 2)));
+        assert_eq!(map.tile_at(Vec2::new(-6.0, 0.0)), None);
+        assert_eq!(map.tile_at(Vec2::new(640.0, 0.0)), None);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let map = TileMap::new(3, 3);
+        let mut corner: Vec<_> = map.neighbors4(0, 0).collect();
+        corner.sort();
+        assert_eq!(corner, vec![(0, 1), (1, 0)]);
+        assert_eq!(map.neighbors4(1, 1).count(), 4);
+        assert_eq!(map.neighbors8(1, 1).count(), 8);
+        assert_eq!(map.neighbors8(2, 2).count(), 3);
+    }
+
+    #[test]
+    fn test_elevation_range_follows_writes() {
+        let mut map = TileMap::new(64, 64);
+        let tile = |x, elevation| MapTile { x, y: 3, terrain: TerrainType::Flat, elevation, slope: 0.0, helium3: 0.0 };
+        map.set(&tile(2, 9.0));
+        map.set(&tile(40, -5.0));
+        assert_eq!(map.elevation_range(), (-5.0, 9.0));
+
+        // Moving an extreme inwards has to find the next one
+        map.set(&tile(2, 1.0));
+        assert_eq!(map.elevation_range(), (-5.0, 1.0));
+        map.set(&tile(40, 0.0));
+        assert_eq!(map.elevation_range(), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_update_slopes() {
+        let mut map = TileMap::new(5, 5);
+        map.set(&MapTile { x: 2, y: 2, terrain: TerrainType::Flat, elevation: 4.0, slope: 0.0, helium3: 0.0 });
+        map.update_slopes(2, 2);
+
+        // A lone bump: flat on top, sloped on the four sides, flat corners
+        // and untouched beyond the neighbours
+        assert_eq!(map.surface(2, 2), Some((TerrainType::Flat, 0.0)));
+        assert_eq!(map.surface(1, 2), Some((TerrainType::Flat, 0.2)));
+        assert_eq!(map.surface(2, 3), Some((TerrainType::Flat, 0.2)));
+        assert_eq!(map.surface(1, 1), Some((TerrainType::Flat, 0.0)));
+        assert_eq!(map.surface(0, 2), Some((TerrainType::Flat, 0.0)));
+    }
+
+    #[test]
+    fn test_writes_mark_chunks_dirty() {
+        let mut map = TileMap::new(64, 64);
+        map.set(&MapTile { x: 40, y: 5, terrain: TerrainType::Rough, elevation: 1.0, slope: 0.2, helium3: 0.0 });
+        assert_eq!(map.take_dirty_chunks(), HashSet::from([(1, 0)]));
+        assert!(map.take_dirty_chunks().is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::{Building, Product};
+use crate::news::{News, NewsKind};
+use crate::simulation::{GameRng, NewDay};
+
+/// Markup on purchases and discount on sales relative to the quoted price
+pub const SPREAD: f32 = 0.05;
+
+/// Share of the gap to the supply-and-demand price closed each day
+const REVERSION: f32 = 0.1;
+
+/// Largest random daily move, as a share of the price
+const VOLATILITY: f32 = 0.04;
+
+/// Units traded that move the price by its whole base value
+const MARKET_DEPTH: f32 = 2_000.0;
+
+/// How much of the recent trading pressure is still felt the next day
+const PRESSURE_DECAY: f32 = 0.9;
+
+/// Daily chance of Earth news shaking up one commodity
+const NEWS_CHANCE: f32 = 0.05;
+
+/// Days of prices kept for the history graph
+pub const PRICE_HISTORY_DAYS: usize = 120;
+
+/// Water and metals recovered per gram of helium-3 from the processed regolith
+pub const WATER_PER_HELIUM3: f32 = 0.05;
+pub const METALS_PER_HELIUM3: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Commodity {
+    Helium3,
+    ResearchData,
+    Water,
+    Metals,
+    Food,
+    SpareParts,
+    Fuel,
+}
+
+impl Commodity {
+    pub const ALL: [Commodity; 7] = [
+        Commodity::Helium3,
+        Commodity::ResearchData,
+        Commodity::Water,
+        Commodity::Metals,
+        Commodity::Food,
+        Commodity::SpareParts,
+        Commodity::Fuel,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Commodity::Helium3 => "Helium-3",
+            Commodity::ResearchData => "Research data",
+            Commodity::Water => "Water",
+            Commodity::Metals => "Metals",
+            Commodity::Food => "Food",
+            Commodity::SpareParts => "Spare parts",
+            Commodity::Fuel => "Fuel",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Commodity::Helium3 => "g",
+            Commodity::ResearchData => "points",
+            Commodity::Water | Commodity::Metals | Commodity::Food | Commodity::Fuel => "t",
+            Commodity::SpareParts => "crates",
+        }
+    }
+
+    /// Long-run price per unit on Earth
+    pub fn base_price(&self) -> f32 {
+        match self {
+            Commodity::Helium3 => 1_200.0,
+            Commodity::ResearchData => 400.0,
+            Commodity::Water => 1_500.0,
+            Commodity::Metals => 900.0,
+            Commodity::Food => 2_500.0,
+            Commodity::SpareParts => 1_000.0,
+            Commodity::Fuel => 1_800.0,
+        }
+    }
+
+    /// What the goods are for on the base
+    pub fn purpose(&self) -> &'static str {
+        match self {
+            Commodity::Helium3 | Commodity::ResearchData | Commodity::Water | Commodity::Metals => {
+                "Produced on the base for sale"
+            }
+            Commodity::Food => "Eaten by the crew instead of rations paid for daily",
+            Commodity::SpareParts => "Each crate fixes a breakdown on the spot",
+            Commodity::Fuel => "Refuels shuttles for the flight home, making launches cheaper",
+        }
+    }
+
+    /// Goods the base produces and sells; the rest are imported
+    pub fn exported(&self) -> bool {
+        matches!(self, Commodity::Helium3 | Commodity::ResearchData | Commodity::Water | Commodity::Metals)
+    }
+}
+
+/// Earth commodity prices. Selling pushes a price down and buying pushes it
+/// up; left alone, prices drift back towards their base with some noise.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Market {
+    prices: HashMap<Commodity, f32>,
+    pressure: HashMap<Commodity, f32>,
+    history: Vec<HashMap<Commodity, f32>>,
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        let prices: HashMap<_, _> = Commodity::ALL.iter().map(|&c| (c, c.base_price())).collect();
+        Self {
+            history: vec![prices.clone()],
+            pressure: Commodity::ALL.iter().map(|&c| (c, 0.0)).collect(),
+            prices,
+        }
+    }
+}
+
+impl Market {
+    pub fn price(&self, commodity: Commodity) -> f32 {
+        self.prices[&commodity]
+    }
+
+    pub fn sell_price(&self, commodity: Commodity) -> f32 {
+        self.price(commodity) * (1.0 - SPREAD)
+    }
+
+    pub fn buy_price(&self, commodity: Commodity) -> f32 {
+        self.price(commodity) * (1.0 + SPREAD)
+    }
+
+    /// Closing prices of past days, oldest first
+    pub fn history(&self, commodity: Commodity) -> impl DoubleEndedIterator<Item = f32> + '_ {
+        self.history.iter().map(move |day| day[&commodity])
+    }
+
+    /// Book a trade: positive quantities were sold to Earth, negative bought
+    pub fn trade(&mut self, commodity: Commodity, quantity: f32) {
+        *self.pressure.get_mut(&commodity).unwrap() += quantity;
+    }
+
+    /// A sudden jump or slump, as news from Earth breaks
+    pub fn shock(&mut self, commodity: Commodity, factor: f32) {
+        *self.prices.get_mut(&commodity).unwrap() *= factor;
+    }
+
+    /// Move every price one day along
+    pub fn update(&mut self, rng: &mut impl Rng) {
+        for commodity in Commodity::ALL {
+            let base = commodity.base_price();
+            let pressure = self.pressure[&commodity];
+            let target = base * (1.0 - pressure / MARKET_DEPTH).clamp(0.3, 3.0);
+            let price = self.prices.get_mut(&commodity).unwrap();
+            *price += (target - *price) * REVERSION + *price * rng.gen_range(-VOLATILITY..=VOLATILITY);
+            *price = price.max(base * 0.1);
+            *self.pressure.get_mut(&commodity).unwrap() *= PRESSURE_DECAY;
+        }
+        self.history.push(self.prices.clone());
+        if self.history.len() > PRICE_HISTORY_DAYS {
+            self.history.remove(0);
+        }
+    }
+}
+
+/// Goods held at the base, apart from what buildings keep on site
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Stockpile {
+    amounts: HashMap<Commodity, f32>,
+}
+
+impl Stockpile {
+    pub fn get(&self, commodity: Commodity) -> f32 {
+        self.amounts.get(&commodity).copied().unwrap_or(0.0)
+    }
+
+    pub fn add(&mut self, commodity: Commodity, amount: f32) {
+        *self.amounts.entry(commodity).or_default() += amount;
+    }
+
+    /// Remove up to `amount`, returning how much there was to take
+    pub fn take(&mut self, commodity: Commodity, amount: f32) -> f32 {
+        let held = self.amounts.entry(commodity).or_default();
+        let taken = amount.min(*held);
+        *held -= taken;
+        taken
+    }
+}
+
+/// Draw up to `amount` of a product out of building storage, returning how
+/// much was found
+pub fn take_stored<'a>(buildings: impl Iterator<Item = Mut<'a, Building>>, product: Product, amount: f32) -> f32 {
+    let mut taken = 0.0;
+    for mut building in buildings {
+        if taken >= amount {
+            break;
+        }
+        if building.kind.product().is_some_and(|(p, _)| p == product) {
+            let share = (amount - taken).min(building.stored);
+            building.stored -= share;
+            taken += share;
+        }
+    }
+    taken
+}
+
+/// Daily price moves, with the odd piece of news from Earth
+pub fn update_market(
+    mut new_day: EventReader<NewDay>,
+    mut market: ResMut<Market>,
+    mut rng: ResMut<GameRng>,
+    mut news: EventWriter<News>,
+) {
+    let rng = &mut rng.0;
+    for _ in new_day.read() {
+        market.update(rng);
+        if rng.gen::<f32>() < NEWS_CHANCE {
+            let commodity = Commodity::ALL[rng.gen_range(0..Commodity::ALL.len())];
+            let factor = rng.gen_range(0.6..1.6);
+            let direction = if factor > 1.0 { "soars" } else { "slumps" };
+            let story = format!("{} {} to ${:.0}", commodity.name(), direction, market.price(commodity) * factor);
+            info!("Earth news: {}", story);
+            news.send(News::new(NewsKind::Market, story));
+            market.shock(commodity, factor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_selling_pushes_price_down() {
+        let mut flooded = Market::default();
+        let mut quiet = Market::default();
+        flooded.trade(Commodity::Helium3, 1_000.0);
+        // Same seed for both, so only the trade sets them apart
+        let (mut a, mut b) = (StdRng::seed_from_u64(7), StdRng::seed_from_u64(7));
+        for _ in 0..10 {
+            flooded.update(&mut a);
+            quiet.update(&mut b);
+        }
+        assert!(flooded.price(Commodity::Helium3) < quiet.price(Commodity::Helium3));
+        assert_eq!(flooded.history(Commodity::Helium3).count(), 11);
+    }
+
+    #[test]
+    fn test_prices_return_towards_base() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut market = Market::default();
+        market.shock(Commodity::Fuel, 2.0);
+        for _ in 0..60 {
+            market.update(&mut rng);
+        }
+        let price = market.price(Commodity::Fuel);
+        assert!(price < 1.5 * Commodity::Fuel.base_price());
+        assert!(market.buy_price(Commodity::Fuel) > market.sell_price(Commodity::Fuel));
+    }
+
+    #[test]
+    fn test_stockpile() {
+        let mut stockpile = Stockpile::default();
+        stockpile.add(Commodity::Water, 3.0);
+        assert_eq!(stockpile.take(Commodity::Water, 5.0), 3.0);
+        assert_eq!(stockpile.get(Commodity::Water), 0.0);
+        assert_eq!(stockpile.take(Commodity::Food, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_take_stored() {
+        let mut world = World::new();
+        for stored in [30.0, 50.0] {
+            let mut mine = Building::new(BuildingKind::HeliumMine, 0, 0, 0.0);
+            mine.stored = stored;
+            world.spawn(mine);
+        }
+        world.spawn(Building::new(BuildingKind::OxygenPlant, 1, 0, 0.0));
+
+        let mut buildings = world.query::<&mut Building>();
+        assert_eq!(take_stored(buildings.iter_mut(&mut world), Product::Helium3, 60.0), 60.0);
+        assert_eq!(take_stored(buildings.iter_mut(&mut world), Product::Helium3, 60.0), 20.0);
+    }
+}
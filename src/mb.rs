@@ -0,0 +1,155 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Directory holding the original DOS game data files
+pub const ORIGINAL_DATA_DIR: &str = "moonbase";
+
+/// Every MB file opens with this text, then the row count and the bytes per
+/// row as little-endian u16s
+pub const SIGNATURE: &[u8] = b"Moonbase version 1.0";
+pub const HEADER_LEN: usize = SIGNATURE.len() + 4;
+
+/// Bit planes in the colour (CL*) files, one per bit of an EGA colour index
+pub const EGA_PLANES: usize = 4;
+
+/// The default 16-colour EGA palette the original game drew with
+pub const EGA_PALETTE: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0xAA],
+    [0x00, 0xAA, 0x00],
+    [0x00, 0xAA, 0xAA],
+    [0xAA, 0x00, 0x00],
+    [0xAA, 0x00, 0xAA],
+    [0xAA, 0x55, 0x00],
+    [0xAA, 0xAA, 0xAA],
+    [0x55, 0x55, 0x55],
+    [0x55, 0x55, 0xFF],
+    [0x55, 0xFF, 0x55],
+    [0x55, 0xFF, 0xFF],
+    [0xFF, 0x55, 0x55],
+    [0xFF, 0x55, 0xFF],
+    [0xFF, 0xFF, 0x55],
+    [0xFF, 0xFF, 0xFF],
+];
+
+#[derive(Debug, PartialEq)]
+pub enum MbError {
+    Io(String),
+    BadHeader,
+    Truncated { expected: usize, len: usize },
+}
+
+impl fmt::Display for MbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MbError::Io(err) => write!(f, "Could not read MB file: {}", err),
+            MbError::BadHeader => write!(f, "MB file does not start with a Moonbase header"),
+            MbError::Truncated { expected, len } => {
+                write!(f, "MB file is {} bytes but its header needs {}", len, expected)
+            }
+        }
+    }
+}
+
+/// A bitmap from the original game's MB files.
+///
+/// Each row stores its bit planes one after another, leftmost pixel in the
+/// high bit. Pixels are palette indices, stored top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MbImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl MbImage {
+    pub fn decode(bytes: &[u8], planes: usize) -> Result<Self, MbError> {
+        if bytes.len() < HEADER_LEN || !bytes.starts_with(SIGNATURE) {
+            return Err(MbError::BadHeader);
+        }
+        let field = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as usize;
+        let (height, row_len) = (field(SIGNATURE.len()), field(SIGNATURE.len() + 2));
+        if row_len % planes != 0 {
+            return Err(MbError::BadHeader);
+        }
+        let expected = HEADER_LEN + height * row_len;
+        if bytes.len() < expected {
+            return Err(MbError::Truncated { expected, len: bytes.len() });
+        }
+
+        let plane_len = row_len / planes;
+        let width = plane_len * 8;
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in bytes[HEADER_LEN..expected].chunks_exact(row_len) {
+            for column in 0..width {
+                let index = (0..planes).fold(0, |index, plane| {
+                    let bit = (row[plane * plane_len + column / 8] >> (7 - column % 8)) & 1;
+                    index | bit << plane
+                });
+                pixels.push(index);
+            }
+        }
+        Ok(Self { width, height, pixels })
+    }
+
+    pub fn open(path: impl AsRef<Path>, planes: usize) -> Result<Self, MbError> {
+        let bytes = std::fs::read(path).map_err(|err| MbError::Io(err.to_string()))?;
+        Self::decode(&bytes, planes)
+    }
+
+    /// The colour title logo
+    pub fn logo_path() -> PathBuf {
+        Path::new(ORIGINAL_DATA_DIR).join("CLLOGO.MB")
+    }
+
+    /// Pixels as RGBA bytes in the EGA palette
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&index| {
+                let [r, g, b] = EGA_PALETTE[index as usize % EGA_PALETTE.len()];
+                [r, g, b, 0xFF]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(rows: u16, row_len: u16) -> Vec<u8> {
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.extend(rows.to_le_bytes());
+        bytes.extend(row_len.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_decode_planes() {
+        // One row of 8 pixels: the first has every plane set, the last only
+        // the highest plane
+        let mut bytes = header(1, 4);
+        bytes.extend([0x80, 0x80, 0x80, 0x81]);
+
+        let image = MbImage::decode(&bytes, EGA_PLANES).unwrap();
+        assert_eq!((image.width, image.height), (8, 1));
+        assert_eq!(image.pixels, vec![15, 0, 0, 0, 0, 0, 0, 8]);
+        assert_eq!(&image.to_rgba()[..4], &[0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(MbImage::decode(b"not a bitmap", EGA_PLANES), Err(MbError::BadHeader));
+        assert_eq!(MbImage::decode(&header(1, 6), EGA_PLANES), Err(MbError::BadHeader));
+        let expected = HEADER_LEN + 8;
+        assert_eq!(MbImage::decode(&header(2, 4), EGA_PLANES), Err(MbError::Truncated { expected, len: HEADER_LEN }));
+    }
+
+    #[test]
+    fn test_original_logo_decodes() {
+        let logo = MbImage::open(MbImage::logo_path(), EGA_PLANES).unwrap();
+        assert_eq!((logo.width, logo.height), (256, 230));
+        assert!(logo.pixels.iter().any(|&index| index != 0));
+    }
+}
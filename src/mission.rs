@@ -1,46 +1,341 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
-use rand::Rng;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use crate::buildings::{Building, BuildingKind, BuildingStatus, PowerCable, Product};
+use crate::construction::ConstructionSite;
+use crate::events::{EventLog, EventRates};
+use crate::finance::Treasury;
+use crate::funding::{Funding, Grant};
+use crate::game_state::{GameState, SelectedTool};
+use crate::history::History;
+use crate::leveling::LevelingJob;
+use crate::maintenance::{MaintenanceBudget, SolarFlare};
+use crate::map::TileMap;
+use crate::logistics::Shipping;
+use crate::market::{Market, Stockpile};
+use crate::news::{News, NewsKind, Ticker};
+use crate::overlay::{Coverage, Overlay};
+use crate::save::{PendingLoad, Progress};
+use crate::simulation::{GameClock, GameRng, LifeSupport, NewDay, Research, Workforce};
+use crate::tilemap::TerrainChunk;
+use crate::terrain::{generate_elevation, generate_helium3, generate_terrain, slopes, TerrainMix};
 
 #[derive(Resource, Clone)]
 pub struct Mission {
     pub id: usize,
     pub name: String,
     pub description: String,
-    pub objectives: Vec<String>,
+    pub objectives: Vec<Objective>,
     pub map_size: (u32, u32),
     pub starting_funds: f32,
+    /// Borrowing available before the base has any revenue to borrow against
+    pub credit_line: f32,
+    /// Yearly interest on loans
+    pub interest_rate: f32,
+    /// Days funds may stay negative before the mission is lost
+    pub grace_days: u32,
+    pub grants: Vec<Grant>,
+    pub event_rates: EventRates,
+    pub starting_crew: u32,
+    pub starting_bulldozers: u32,
+    pub seed: u64,
+    pub terrain_mix: TerrainMix,
 }
 
 impl Mission {
-    pub fn load(mission_id: usize) -> Self {
-        // For now, return hardcoded data for Mission 1
-        match mission_id {
-            1 => Self {
-                id: 1,
-                name: "First Steps".to_string(),
-                description: "Establish your first lunar base with basic facilities.".to_string(),
+    /// Missions offered in the New Game dialog, numbered from 1
+    pub const COUNT: usize = 6;
+
+    pub fn load(mission_id: usize) -> Result<Self, String> {
+        let first_steps = Self::first_steps();
+        let mission = match mission_id {
+            1 => first_steps,
+            2 => Self {
+                id: 2,
+                name: "Power Grid".to_string(),
+                description: "Keep a growing base powered across rough highland terrain.".to_string(),
+                objectives: vec![
+                    Objective::new("Build a Solar Array", Milestone::Built(BuildingKind::SolarArray)),
+                    Objective::new("Connect power supply", Milestone::Powered),
+                    Objective::new("Open a Science Lab", Milestone::Built(BuildingKind::ScienceLab)),
+                ],
+                starting_funds: 800_000.0,
+                terrain_mix: TerrainMix { rough: 0.25, mare: 0.15, ..default() },
+                ..first_steps
+            },
+            3 => Self {
+                id: 3,
+                name: "Life Support".to_string(),
+                description: "Supply oxygen for a larger crew through a season of solar flares.".to_string(),
+                objectives: vec![
+                    Objective::new("Build an Oxygen Plant", Milestone::Built(BuildingKind::OxygenPlant)),
+                    Objective::new("Establish oxygen production", Milestone::Producing(Product::Oxygen)),
+                    Objective::new("Grow the crew to 16", Milestone::Crew(16)),
+                ],
+                starting_crew: 12,
+                event_rates: EventRates { solar_flare: 0.05, ..default() },
+                ..first_steps
+            },
+            4 => Self {
+                id: 4,
+                name: "Research".to_string(),
+                description: "Turn the base into a working observatory and laboratory.".to_string(),
+                objectives: vec![
+                    Objective::new("Open a Science Lab", Milestone::Built(BuildingKind::ScienceLab)),
+                    Objective::new("Build a Telescope", Milestone::Built(BuildingKind::Telescope)),
+                    Objective::new("Collect 500 research points", Milestone::Research(500.0)),
+                ],
+                starting_funds: 1_200_000.0,
+                ..first_steps
+            },
+            5 => Self {
+                id: 5,
+                name: "Mining".to_string(),
+                description: "Mine helium-3 from the cratered uplands and ship it home.".to_string(),
                 objectives: vec![
-                    "Build a Living Module".to_string(),
-                    "Connect power supply".to_string(),
-                    "Establish oxygen production".to_string(),
+                    Objective::new("Build a Helium-3 Mine", Milestone::Built(BuildingKind::HeliumMine)),
+                    Objective::new("Produce helium-3", Milestone::Producing(Product::Helium3)),
+                    Objective::new("Build a Landing Pad", Milestone::Built(BuildingKind::LandingPad)),
                 ],
-                map_size: (64, 64),
-                starting_funds: 1000000.0,
+                starting_bulldozers: 2,
+                terrain_mix: TerrainMix { crater: 0.18, mare: 0.20, ..default() },
+                ..first_steps
             },
-            _ => panic!("Mission {} not implemented yet", mission_id),
+            6 => Self {
+                id: 6,
+                name: "Full Operations".to_string(),
+                description: "Run a self-sufficient base under a heavier meteor shower.".to_string(),
+                objectives: vec![
+                    Objective::new("Establish oxygen production", Milestone::Producing(Product::Oxygen)),
+                    Objective::new("Produce helium-3", Milestone::Producing(Product::Helium3)),
+                    Objective::new("Collect 1000 research points", Milestone::Research(1000.0)),
+                    Objective::new("Grow the crew to 32", Milestone::Crew(32)),
+                ],
+                starting_funds: 1_500_000.0,
+                starting_crew: 12,
+                starting_bulldozers: 2,
+                event_rates: EventRates { meteor: 0.02, ..default() },
+                ..first_steps
+            },
+            _ => return Err(format!("Mission {} does not exist", mission_id)),
+        };
+        Ok(mission)
+    }
+
+    fn first_steps() -> Self {
+        Self {
+            id: 1,
+            name: "First Steps".to_string(),
+            description: "Establish your first lunar base with basic facilities.".to_string(),
+            objectives: vec![
+                Objective::new("Build a Living Module", Milestone::Built(BuildingKind::LivingModule)),
+                Objective::new("Connect power supply", Milestone::Powered),
+                Objective::new("Establish oxygen production", Milestone::Producing(Product::Oxygen)),
+            ],
+            map_size: (64, 64),
+            starting_funds: 1000000.0,
+            credit_line: 500_000.0,
+            interest_rate: 0.08,
+            grace_days: 30,
+            grants: vec![
+                Grant::new("Lunar Development Agency", Milestone::Built(BuildingKind::LivingModule), 150_000.0),
+                Grant::new("Lunar Development Agency", Milestone::Built(BuildingKind::OxygenPlant), 200_000.0),
+                Grant::new("Selene Foundation", Milestone::Research(100.0), 100_000.0),
+                Grant::new("Helios Energy", Milestone::Built(BuildingKind::HeliumMine), 250_000.0),
+                Grant::new("Artemis Trust", Milestone::Crew(16), 150_000.0),
+            ],
+            event_rates: EventRates::default(),
+            starting_crew: 8,
+            starting_bulldozers: 1,
+            seed: 1,
+            terrain_mix: TerrainMix::default(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_map_size(mut self, map_size: (u32, u32)) -> Self {
+        self.map_size = map_size;
+        self
+    }
+}
+
+/// Something the base has achieved, for grants and objectives to check
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Milestone {
+    /// A building of this kind up and running
+    Built(BuildingKind),
+    /// A building drawing power from a working grid
+    Powered,
+    Producing(Product),
+    Crew(u32),
+    Research(f32),
+}
+
+impl Milestone {
+    pub fn reached(&self, progress: &BaseProgress) -> bool {
+        match *self {
+            Milestone::Built(kind) => progress.built.contains(&kind),
+            Milestone::Powered => progress.powered,
+            Milestone::Producing(product) => progress.producing.contains(&product),
+            Milestone::Crew(count) => progress.crew >= count,
+            Milestone::Research(points) => progress.research >= points,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Milestone::Built(kind) => format!("Build a {}", kind.name()),
+            Milestone::Powered => "Connect power supply".to_string(),
+            Milestone::Producing(product) => format!("Produce {}", product.unit()),
+            Milestone::Crew(count) => format!("Grow the crew to {}", count),
+            Milestone::Research(points) => format!("Collect {:.0} research points", points),
         }
     }
 }
 
-#[derive(Component)]
+/// What the base has to show for itself, for milestones to be checked against
+#[derive(Default)]
+pub struct BaseProgress {
+    pub built: Vec<BuildingKind>,
+    pub powered: bool,
+    pub producing: Vec<Product>,
+    pub crew: u32,
+    pub research: f32,
+}
+
+impl BaseProgress {
+    pub fn survey<'a>(
+        buildings: impl Iterator<Item = (&'a Building, Option<&'a BuildingStatus>)>,
+        crew: u32,
+        research: f32,
+    ) -> Self {
+        let mut progress = Self { crew, research, ..default() };
+        for (building, status) in buildings.filter(|(building, _)| building.operating()) {
+            progress.built.push(building.kind);
+            let Some(status) = status else {
+                continue;
+            };
+            progress.powered |= status.powered && building.kind.power() < 0.0;
+            if let Some((product, _)) = building.kind.product().filter(|_| status.output > 0.0) {
+                progress.producing.push(product);
+            }
+        }
+        progress
+    }
+}
+
+/// A goal the mission sets, in the words of the briefing
+#[derive(Clone, Debug, PartialEq)]
+pub struct Objective {
+    pub description: String,
+    pub milestone: Milestone,
+}
+
+impl Objective {
+    pub fn new(description: &str, milestone: Milestone) -> Self {
+        Self { description: description.to_string(), milestone }
+    }
+}
+
+/// Day each of the mission's objectives was met, by index
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct CompletedObjectives {
+    pub days: Vec<(usize, u32)>,
+}
+
+impl CompletedObjectives {
+    pub fn day(&self, objective: usize) -> Option<u32> {
+        self.days.iter().find(|&&(index, _)| index == objective).map(|&(_, day)| day)
+    }
+}
+
+/// Tick off objectives as the base reaches them
+pub fn track_objectives(
+    mut new_day: EventReader<NewDay>,
+    clock: Res<GameClock>,
+    mission: Res<Mission>,
+    mut completed: ResMut<CompletedObjectives>,
+    research: Res<Research>,
+    workforce: Res<Workforce>,
+    buildings: Query<(&Building, Option<&BuildingStatus>)>,
+    mut news: EventWriter<News>,
+) {
+    if new_day.read().count() == 0 {
+        return;
+    }
+    let progress = BaseProgress::survey(buildings.iter(), workforce.crew, research.points);
+    for (index, objective) in mission.objectives.iter().enumerate() {
+        if completed.day(index).is_some() || !objective.milestone.reached(&progress) {
+            continue;
+        }
+        info!("Objective complete: {}", objective.description);
+        completed.days.push((index, clock.day));
+        news.send(News::new(NewsKind::Objective, format!("Objective complete: {}", objective.description)));
+        if completed.days.len() == mission.objectives.len() {
+            news.send(News::new(NewsKind::Objective, format!("All objectives of {} complete!", mission.name)));
+        }
+    }
+}
+
+/// The mission has been lost and the simulation stops
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct MissionFailed {
+    pub reason: String,
+}
+
+/// Turn the text typed into the New Game dialog into a map seed.
+///
+/// Numbers are used as-is; anything else is hashed (FNV-1a) so that words
+/// like "tranquility" make shareable seeds too.
+pub fn seed_from_text(text: &str) -> u64 {
+    let text = text.trim();
+    if let Ok(seed) = text.parse::<u64>() {
+        return seed;
+    }
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// One tile's surface, as read from or written to the `TileMap`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MapTile {
     pub x: u32,
     pub y: u32,
     pub terrain: TerrainType,
+    /// Height above the mission datum in metres
+    pub elevation: f32,
+    /// Grade derived from the neighbouring elevations (1.0 = 45 degrees)
+    pub slope: f32,
+    /// Helium-3 in the regolith, parts per billion
+    pub helium3: f32,
+}
+
+#[derive(Resource)]
+pub struct MapDisplay {
+    pub shading: bool,
+    pub contours: bool,
+    pub grid: bool,
+    pub overlay: Overlay,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+impl Default for MapDisplay {
+    fn default() -> Self {
+        Self {
+            shading: true,
+            contours: false,
+            grid: true,
+            overlay: Overlay::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TerrainType {
     Flat,
     Rough,
@@ -63,10 +358,23 @@ pub fn setup_mission(
     mut commands: Commands,
     mission_id: Res<crate::ui::NewGameDialog>,
     mut camera: Query<&mut Transform, With<Camera2d>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    pending: Option<Res<PendingLoad>>,
 ) {
-    let mission = Mission::load(mission_id.selected_mission);
-    commands.insert_resource(mission.clone());
-    
+    let mission = match Mission::load(mission_id.selected_mission) {
+        Ok(mission) => mission,
+        Err(err) => {
+            error!("{}", err);
+            commands.remove_resource::<PendingLoad>();
+            next_state.set(GameState::MainMenu);
+            return;
+        }
+    };
+    let mission = mission
+        .with_seed(seed_from_text(&mission_id.seed_text))
+        .with_map_size((mission_id.map_size, mission_id.map_size));
+    info!("Starting mission {}: {} - {} (seed {})", mission.id, mission.name, mission.description, mission.seed);
+
     // Reset camera position to center of map
     if let Ok(mut transform) = camera.get_single_mut() {
         let (width, height) = mission.map_size;
@@ -74,106 +382,168 @@ pub fn setup_mission(
         transform.translation.y = (height as f32 * 10.0) / 2.0;
         transform.scale = Vec3::splat(crate::DEFAULT_ZOOM);
     }
+
+    let progress = pending.map(|pending| pending.0.clone());
+    commands.remove_resource::<PendingLoad>();
+    start_mission(&mut commands, mission, progress);
+}
+
+/// Insert the mission's starting resources and spawn its map, then restore
+/// the progress from a save if one is being loaded
+pub fn start_mission(commands: &mut Commands, mission: Mission, progress: Option<Progress>) {
+    commands.insert_resource(Treasury::new(mission.starting_funds));
+    commands.insert_resource(Workforce {
+        crew: mission.starting_crew,
+        bulldozers: mission.starting_bulldozers,
+    });
+    commands.insert_resource(GameClock::default());
+    commands.insert_resource(Research::default());
+    commands.insert_resource(History::default());
+    commands.insert_resource(MaintenanceBudget::default());
+    commands.insert_resource(Market::default());
+    commands.insert_resource(Stockpile::default());
+    commands.insert_resource(Shipping::default());
+    commands.insert_resource(Funding::default());
+    commands.insert_resource(GameRng::new(mission.seed));
+    commands.insert_resource(EventLog::default());
+    commands.insert_resource(CompletedObjectives::default());
+    commands.insert_resource(LifeSupport::default());
+    commands.insert_resource(Ticker::default());
+    commands.insert_resource(Coverage::default());
+    commands.remove_resource::<SolarFlare>();
+    commands.remove_resource::<MissionFailed>();
     
     // Generate map
-    let mut rng = rand::thread_rng();
     let (width, height) = mission.map_size;
+    let terrain = generate_terrain(width, height, mission.seed, &mission.terrain_mix);
+    let elevation = generate_elevation(width, height, mission.seed, &terrain);
+    let slope = slopes(&elevation, width, height);
+    let helium3 = generate_helium3(width, height, mission.seed, &terrain);
     
-    for y in 0..height {
-        for x in 0..width {
-            let terrain = match rng.gen_range(0..100) {
-                0..=70 => TerrainType::Flat,    // 70% chance
-                71..=85 => TerrainType::Rough,  // 15% chance
-                86..=95 => TerrainType::Crater, // 10% chance
-                _ => TerrainType::Mountain,     // 5% chance
-            };
-            
-            let position = Vec3::new(
-                x as f32 * 10.0,
-                y as f32 * 10.0,
-                0.0
-            );
-            
-            commands.spawn((
-                MapTile { x, y, terrain },
-                SpatialBundle {
-                    transform: Transform::from_translation(position),
-                    ..default()
-                },
-            ));
-        }
+    let mut tile_map = TileMap::from_layers(width, height, &terrain, &elevation, &slope, &helium3);
+    if let Some(progress) = progress {
+        progress.restore(commands, &mut tile_map, mission.seed);
     }
+    commands.insert_resource(tile_map);
+    commands.insert_resource(mission);
 }
 
-pub fn draw_map(
-    mut gizmos: Gizmos,
-    tiles: Query<(&MapTile, &Transform)>,
-    mission: Res<Mission>,
+/// Remove everything a mission put in the world, so the title screen starts
+/// clean and the next mission doesn't inherit the last one's base
+pub fn teardown_mission(
+    mut commands: Commands,
+    entities: Query<
+        Entity,
+        Or<(With<TerrainChunk>, With<Building>, With<ConstructionSite>, With<PowerCable>, With<LevelingJob>)>,
+    >,
 ) {
-    // Draw tiles
-    for (tile, transform) in tiles.iter() {
-        let color = match tile.terrain {
-            TerrainType::Flat => Color::GRAY,
-            TerrainType::Rough => Color::DARK_GRAY,
-            TerrainType::Crater => Color::BLACK,
-            TerrainType::Mountain => Color::WHITE,
-        };
-        
-        gizmos.rect_2d(
-            Vec2::new(transform.translation.x, transform.translation.y),
-            0.0,
-            Vec2::new(9.0, 9.0),
-            color,
-        );
-    }
-
-    // Draw grid
-    let (width, height) = mission.map_size;
-    for x in 0..=width {
-        gizmos.line_2d(
-            Vec2::new(x as f32 * 10.0, 0.0),
-            Vec2::new(x as f32 * 10.0, height as f32 * 10.0),
-            Color::rgba(1.0, 1.0, 1.0, 0.2),
-        );
-    }
-    for y in 0..=height {
-        gizmos.line_2d(
-            Vec2::new(0.0, y as f32 * 10.0),
-            Vec2::new(width as f32 * 10.0, y as f32 * 10.0),
-            Color::rgba(1.0, 1.0, 1.0, 0.2),
-        );
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
     }
+    commands.remove_resource::<Mission>();
+    commands.remove_resource::<TileMap>();
+    commands.remove_resource::<Treasury>();
+    commands.remove_resource::<Workforce>();
+    commands.remove_resource::<GameClock>();
+    commands.remove_resource::<Research>();
+    commands.remove_resource::<History>();
+    commands.remove_resource::<MaintenanceBudget>();
+    commands.remove_resource::<Market>();
+    commands.remove_resource::<Stockpile>();
+    commands.remove_resource::<Shipping>();
+    commands.remove_resource::<Funding>();
+    commands.remove_resource::<GameRng>();
+    commands.remove_resource::<EventLog>();
+    commands.remove_resource::<CompletedObjectives>();
+    commands.remove_resource::<LifeSupport>();
+    commands.remove_resource::<Ticker>();
+    commands.remove_resource::<SolarFlare>();
+    commands.remove_resource::<MissionFailed>();
+    commands.remove_resource::<SelectedTool>();
+    commands.insert_resource(Coverage::default());
+    commands.insert_resource(crate::ui::InspectedBuilding::default());
 }
 
 pub fn handle_tile_hover(
+    mut contexts: EguiContexts,
     windows: Query<&Window, With<PrimaryWindow>>,
     camera: Query<(&Camera, &GlobalTransform)>,
-    tiles: Query<&MapTile>,
+    tile_map: Res<TileMap>,
+    buildings: Query<(&Building, &BuildingStatus)>,
+    sites: Query<&ConstructionSite>,
+    cables: Query<&PowerCable>,
     mut gizmos: Gizmos,
 ) {
     let (camera, camera_transform) = camera.single();
     let window = windows.single();
+    let ctx = contexts.ctx_mut();
+    if ctx.is_pointer_over_area() {
+        return;
+    }
     
     if let Some(cursor_pos) = window.cursor_position() {
         if let Some(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) {
-            let tile_x = (world_pos.x / 10.0).floor() as u32;
-            let tile_y = (world_pos.y / 10.0).floor() as u32;
-            
             // Find the tile under cursor
-            if tiles.iter().any(|t| t.x == tile_x && t.y == tile_y) {
+            if let Some(tile) = tile_map.tile_at(world_pos).and_then(|(x, y)| tile_map.tile(x, y)) {
                 // Highlight hovered tile
                 gizmos.rect_2d(
-                    Vec2::new(tile_x as f32 * 10.0, tile_y as f32 * 10.0),
+                    Vec2::new(tile.x as f32 * 10.0, tile.y as f32 * 10.0),
                     0.0,
                     Vec2::new(10.0, 10.0),
                     Color::rgba(1.0, 1.0, 0.0, 0.3),
                 );
-                
-                // TODO: Show terrain info in a tooltip instead of console logging
+
+                let building = buildings.iter().find(|(b, _)| (b.x, b.y) == (tile.x, tile.y));
+                let site = sites.iter().find(|s| (s.building.x, s.building.y) == (tile.x, tile.y));
+                let cabled = cables.iter().any(|c| (c.x, c.y) == (tile.x, tile.y));
+                egui::show_tooltip_at_pointer(ctx, egui::Id::new("tile_tooltip"), |ui| {
+                    ui.strong(format!("Tile ({}, {})", tile.x, tile.y));
+                    ui.label(tile.terrain.description());
+                    ui.label(format!("Elevation: {:.1} m, slope {:.0}%", tile.elevation, tile.slope * 100.0));
+                    ui.label(format!("Helium-3: {:.0} ppb", tile.helium3));
+                    if let Some((building, status)) = building {
+                        ui.separator();
+                        ui.strong(building.kind.name());
+                        let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+                        ui.label(format!("Powered: {}", yes_no(status.powered)));
+                        ui.label(format!("Staffed: {}", yes_no(status.staffed)));
+                        ui.label(format!("Health: {:.0}%", building.health));
+                        if building.broken {
+                            ui.colored_label(egui::Color32::RED, "Broken down");
+                        }
+                        match building.kind.product() {
+                            Some((product, _)) => {
+                                ui.label(format!("Output: {:.1} {}/day", status.output, product.unit()));
+                            }
+                            None if building.kind.power() > 0.0 => {
+                                ui.label(format!("Output: {:.0} kW", building.kind.power()));
+                            }
+                            None => {}
+                        }
+                    } else if let Some(site) = site {
+                        ui.separator();
+                        ui.strong(format!("{} (under construction)", site.kind().name()));
+                        ui.label(format!("Progress: {:.0}% of {:.1} days", site.done() * 100.0, site.work_days));
+                        if site.worker.is_none() {
+                            ui.label("Waiting for a crew or bulldozer");
+                        } else if site.travel > 0.0 {
+                            ui.label(format!("Bulldozer arriving in {:.1} days", site.travel));
+                        }
+                        if site.powered {
+                            ui.label("Supplied by the grid");
+                        } else {
+                            ui.label(format!("Materials: {:.0}/{:.0} t", site.delivered, site.kind().materials()));
+                        }
+                    } else if cabled {
+                        ui.separator();
+                        ui.label("Power cable");
+                    }
+                });
             }
         }
     }
-} 
+}
+ 
 
 #[cfg(test)]
 mod tests {
@@ -181,7 +551,7 @@ mod tests {
 
     #[test]
     fn test_mission_load() {
-        let mission = Mission::load(1);
+        let mission = Mission::load(1).unwrap();
         assert_eq!(mission.id, 1);
         assert_eq!(mission.name, "First Steps");
         assert_eq!(mission.map_size, (64, 64));
@@ -190,9 +560,19 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Mission 999 not implemented yet")]
     fn test_mission_load_invalid() {
-        Mission::load(999);
+        assert_eq!(Mission::load(999).err(), Some("Mission 999 does not exist".to_string()));
+    }
+
+    #[test]
+    fn test_every_offered_mission_loads() {
+        for id in 1..=Mission::COUNT {
+            let mission = Mission::load(id).unwrap();
+            assert_eq!(mission.id, id);
+            assert!(!mission.objectives.is_empty());
+        }
+        assert_eq!(Mission::load(0).err(), Some("Mission 0 does not exist".to_string()));
+        assert!(Mission::load(Mission::COUNT + 1).is_err());
     }
 
     #[test]
@@ -209,34 +589,122 @@ mod tests {
             x: 10,
             y: 20,
             terrain: TerrainType::Flat,
+            elevation: 12.5,
+            slope: 0.1,
+            helium3: 20.0,
         };
         
         assert_eq!(tile.x, 10);
         assert_eq!(tile.y, 20);
         assert_eq!(tile.terrain, TerrainType::Flat);
+        assert_eq!(tile.elevation, 12.5);
+        assert_eq!(tile.slope, 0.1);
+        assert_eq!(tile.helium3, 20.0);
+    }
+
+    #[test]
+    fn test_large_map_setup() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_state::<GameState>()
+            .add_systems(Startup, setup_mission);
+        app.insert_resource(crate::ui::NewGameDialog {
+            open: false,
+            selected_mission: 1,
+            seed_text: "7".to_string(),
+            map_size: 1024,
+            restart: false,
+        });
+        app.update();
+
+        let tile_map = app.world.resource::<TileMap>();
+        assert_eq!(app.world.resource::<Mission>().map_size, (1024, 1024));
+        assert!(tile_map.terrain(1023, 1023).is_some());
+        assert_eq!(app.world.entities().len(), 0);
+    }
+
+    #[test]
+    fn test_unknown_mission_returns_to_menu() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_state::<GameState>()
+            .insert_resource(crate::ui::NewGameDialog { selected_mission: 7, ..default() })
+            .add_systems(Startup, setup_mission);
+        app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+
+        assert!(app.world.get_resource::<Mission>().is_none());
+        assert!(app.world.get_resource::<TileMap>().is_none());
+        assert_eq!(app.world.resource::<State<GameState>>().get(), &GameState::MainMenu);
+    }
+
+    #[test]
+    fn test_teardown_clears_the_mission() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_state::<GameState>()
+            .init_resource::<crate::ui::NewGameDialog>()
+            .add_systems(Startup, setup_mission)
+            .add_systems(Update, teardown_mission);
+        app.update();
+        app.world.spawn(Building::new(BuildingKind::LivingModule, 0, 0, 0.0));
+        app.update();
+
+        assert!(app.world.get_resource::<Mission>().is_none());
+        assert!(app.world.get_resource::<TileMap>().is_none());
+        assert!(app.world.get_resource::<Treasury>().is_none());
+        assert_eq!(app.world.entities().len(), 0);
     }
 
     #[test]
     fn test_mission_objectives() {
-        let mission = Mission::load(1);
-        assert!(mission.objectives.contains(&"Build a Living Module".to_string()));
-        assert!(mission.objectives.contains(&"Connect power supply".to_string()));
-        assert!(mission.objectives.contains(&"Establish oxygen production".to_string()));
+        let mission = Mission::load(1).unwrap();
+        let objectives: Vec<_> = mission.objectives.iter().map(|o| o.description.as_str()).collect();
+        assert!(objectives.contains(&"Build a Living Module"));
+        assert!(objectives.contains(&"Connect power supply"));
+        assert!(objectives.contains(&"Establish oxygen production"));
         assert_eq!(mission.objectives.len(), 3);
     }
 
+    #[test]
+    fn test_objectives_completed() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .add_event::<News>()
+            .insert_resource(Mission::load(1).unwrap())
+            .insert_resource(Workforce { crew: 8, bulldozers: 1 })
+            .init_resource::<GameClock>()
+            .init_resource::<Research>()
+            .init_resource::<CompletedObjectives>()
+            .add_systems(Update, track_objectives);
+        app.world.spawn(Building::new(BuildingKind::LivingModule, 0, 0, 0.0));
+        let running = BuildingStatus { powered: true, staffed: true, output: 50.0 };
+        app.world.spawn((Building::new(BuildingKind::OxygenPlant, 1, 0, 0.0), running));
+
+        app.world.send_event(NewDay);
+        app.update();
+        let completed = app.world.resource::<CompletedObjectives>();
+        assert!((0..3).all(|objective| completed.day(objective) == Some(0)));
+        // One story per objective and one for finishing them all
+        assert_eq!(app.world.resource::<Events<News>>().len(), 4);
+    }
+
     #[test]
     fn test_map_generation() {
         let mut app = App::new();
         
         // Setup minimal app with required systems
         app.add_plugins(MinimalPlugins)
+            .add_state::<GameState>()
             .add_systems(Startup, setup_mission);
         
         // Add required resource
         app.insert_resource(crate::ui::NewGameDialog {
             open: false,
             selected_mission: 1,
+            seed_text: "42".to_string(),
+            map_size: 64,
+            restart: false,
         });
         
         // Run systems
@@ -246,15 +714,16 @@ mod tests {
         let mission = app.world.get_resource::<Mission>().expect("Mission should be created");
         assert_eq!(mission.id, 1);
         
-        // Verify map tiles were created
-        let tile_count = app.world.query::<&MapTile>().iter(&app.world).count();
-        assert_eq!(tile_count, 64 * 64); // Based on mission 1 map size
+        // Verify the map was stored, without an entity per tile
+        let tile_map = app.world.resource::<TileMap>();
+        assert_eq!((tile_map.width, tile_map.height), (64, 64)); // Based on mission 1 map size
+        assert_eq!(app.world.entities().len(), 0);
         
         // Verify terrain distribution
-        let terrain_counts = app.world.query::<&MapTile>()
-            .iter(&app.world)
-            .fold((0, 0, 0, 0), |mut acc, tile| {
-                match tile.terrain {
+        let terrain_counts = (0..64 * 64)
+            .filter_map(|i| tile_map.terrain(i % 64, i / 64))
+            .fold((0, 0, 0, 0), |mut acc, terrain| {
+                match terrain {
                     TerrainType::Flat => acc.0 += 1,
                     TerrainType::Rough => acc.1 += 1,
                     TerrainType::Crater => acc.2 += 1,
@@ -276,6 +745,41 @@ mod tests {
         assert!(mountain_percentage > 0.02 && mountain_percentage < 0.08); // ~5%
     }
 
+    fn generate_terrain(seed_text: &str) -> Vec<(u32, u32, TerrainType)> {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_state::<GameState>()
+            .add_systems(Startup, setup_mission);
+        app.insert_resource(crate::ui::NewGameDialog {
+            open: false,
+            selected_mission: 1,
+            seed_text: seed_text.to_string(),
+            map_size: 64,
+            restart: false,
+        });
+        app.update();
+
+        let tile_map = app.world.resource::<TileMap>();
+        (0..64 * 64)
+            .filter_map(|i| tile_map.tile(i % 64, i / 64))
+            .map(|tile| (tile.x, tile.y, tile.terrain))
+            .collect()
+    }
+
+    #[test]
+    fn test_map_generation_is_seeded() {
+        assert_eq!(generate_terrain("1234"), generate_terrain("1234"));
+        assert_ne!(generate_terrain("1234"), generate_terrain("4321"));
+    }
+
+    #[test]
+    fn test_seed_from_text() {
+        assert_eq!(seed_from_text("1990"), 1990);
+        assert_eq!(seed_from_text(" 7 "), 7);
+        assert_eq!(seed_from_text("tranquility"), seed_from_text("tranquility"));
+        assert_ne!(seed_from_text("tranquility"), seed_from_text("serenity"));
+    }
+
     #[test]
     fn test_terrain_type_debug() {
         assert_eq!(format!("{:?}", TerrainType::Flat), "Flat");
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::construction::ConstructionFinished;
+use crate::events::RandomEvent;
+use crate::simulation::GameClock;
+
+/// Stories kept on the ticker; older ones drop off the end
+pub const TICKER_LENGTH: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewsKind {
+    Construction,
+    Shipment,
+    Market,
+    Disaster,
+    Opportunity,
+    Objective,
+}
+
+impl NewsKind {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            NewsKind::Construction => "🏗",
+            NewsKind::Shipment => "🚀",
+            NewsKind::Market => "💹",
+            NewsKind::Disaster => "⚠",
+            NewsKind::Opportunity => "★",
+            NewsKind::Objective => "✔",
+        }
+    }
+}
+
+/// A story for the news ticker, sent by whatever made it happen. Stories
+/// with a location take the camera there when clicked.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct News {
+    pub kind: NewsKind,
+    pub text: String,
+    pub location: Option<(u32, u32)>,
+}
+
+impl News {
+    pub fn new(kind: NewsKind, text: impl Into<String>) -> Self {
+        Self { kind, text: text.into(), location: None }
+    }
+
+    pub fn at(mut self, x: u32, y: u32) -> Self {
+        self.location = Some((x, y));
+        self
+    }
+}
+
+/// The latest stories with the day they broke, and how far the ticker has
+/// scrolled
+#[derive(Resource, Default)]
+pub struct Ticker {
+    pub items: VecDeque<(u32, News)>,
+    pub offset: f32,
+}
+
+impl Ticker {
+    pub fn push(&mut self, day: u32, news: News) {
+        self.items.push_back((day, news));
+        while self.items.len() > TICKER_LENGTH {
+            self.items.pop_front();
+        }
+    }
+}
+
+/// Pass finished construction on to the news
+pub fn report_construction(mut finished: EventReader<ConstructionFinished>, mut news: EventWriter<News>) {
+    for event in finished.read() {
+        let text = format!("{} completed", event.kind.name());
+        news.send(News::new(NewsKind::Construction, text).at(event.x, event.y));
+    }
+}
+
+/// Pass disasters and windfalls on to the news
+pub fn report_random_events(mut random_events: EventReader<RandomEvent>, mut news: EventWriter<News>) {
+    for event in random_events.read() {
+        let kind = if event.is_disaster() { NewsKind::Disaster } else { NewsKind::Opportunity };
+        let story = News::new(kind, event.description());
+        news.send(match *event {
+            RandomEvent::MeteorStrike { x, y, .. } => story.at(x, y),
+            _ => story,
+        });
+    }
+}
+
+/// Put the day's stories on the ticker
+pub fn collect_news(mut news: EventReader<News>, clock: Res<GameClock>, mut ticker: ResMut<Ticker>) {
+    for story in news.read() {
+        ticker.push(clock.day, story.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+
+    #[test]
+    fn test_events_reach_the_ticker() {
+        let mut app = App::new();
+        app.add_event::<News>()
+            .add_event::<ConstructionFinished>()
+            .add_event::<RandomEvent>()
+            .init_resource::<GameClock>()
+            .init_resource::<Ticker>()
+            .add_systems(Update, ((report_construction, report_random_events), collect_news).chain());
+        let entity = app.world.spawn_empty().id();
+        app.world.send_event(ConstructionFinished { entity, kind: BuildingKind::ScienceLab, x: 3, y: 4 });
+        app.world.send_event(RandomEvent::FundingBonus { amount: 50_000.0 });
+        app.update();
+
+        let ticker = app.world.resource::<Ticker>();
+        let stories: Vec<_> = ticker.items.iter().map(|(_, news)| (news.kind, news.location)).collect();
+        assert!(stories.contains(&(NewsKind::Construction, Some((3, 4)))));
+        assert!(stories.contains(&(NewsKind::Opportunity, None)));
+    }
+
+    #[test]
+    fn test_ticker_keeps_the_latest() {
+        let mut ticker = Ticker::default();
+        for day in 0..TICKER_LENGTH as u32 + 3 {
+            ticker.push(day, News::new(NewsKind::Market, "Prices move"));
+        }
+        assert_eq!(ticker.items.len(), TICKER_LENGTH);
+        assert_eq!(ticker.items.front().map(|(day, _)| *day), Some(3));
+    }
+}
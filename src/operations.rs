@@ -0,0 +1,591 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::buildings::{
+    Building, BuildingStatus, Demolish, PowerCable, Product, DEMOLITION_REFUND, RATED_HELIUM3_PPB,
+};
+use crate::construction::{ConstructionSite, SITE_POWER};
+use crate::finance::{Account, Treasury, RATION_COST, SALARY_PER_CREW};
+use crate::history::{Action, History, Structure};
+use crate::maintenance::MaintenanceBudget;
+use crate::market::{self, Commodity, Stockpile, METALS_PER_HELIUM3, WATER_PER_HELIUM3};
+use crate::news::{News, NewsKind};
+use crate::leveling::{LevelingJob, Worker};
+use crate::map::TileMap;
+use crate::simulation::{GameClock, LifeSupport, NewDay, Workforce, FOOD_PER_CREW, LANDER_HOUSING, OXYGEN_PER_CREW};
+
+/// Split cable and building tiles into separate power networks. Anything on
+/// a tile conducts to the four tiles around it. Returns the network id of
+/// every conducting tile.
+pub fn power_networks(conductors: &HashSet<(u32, u32)>) -> HashMap<(u32, u32), usize> {
+    let mut network_of: HashMap<(u32, u32), usize> = HashMap::new();
+    let mut next_id = 0;
+    for &start in conductors {
+        if network_of.contains_key(&start) {
+            continue;
+        }
+        let mut stack = vec![start];
+        network_of.insert(start, next_id);
+        while let Some((x, y)) = stack.pop() {
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for next in neighbors {
+                if conductors.contains(&next) && !network_of.contains_key(&next) {
+                    network_of.insert(next, next_id);
+                    stack.push(next);
+                }
+            }
+        }
+        next_id += 1;
+    }
+    network_of
+}
+
+/// Work out which buildings are staffed and powered, and what they produce.
+///
+/// Crew not out leveling, building or on maintenance rounds are put on shift
+/// in build order until they run out; switched-off and broken-down buildings
+/// get none and take no part in the grid.
+/// A network is powered when its staffed generators cover the draw of its
+/// staffed consumers; otherwise every consumer on it browns out.
+/// Construction sites next to a network with `SITE_POWER` to spare are
+/// powered too, though they don't draw from the balance.
+pub fn update_building_status(
+    workforce: Res<Workforce>,
+    budget: Res<MaintenanceBudget>,
+    tile_map: Res<TileMap>,
+    jobs: Query<&LevelingJob>,
+    cables: Query<&PowerCable>,
+    mut buildings: Query<(Entity, &Building, &mut BuildingStatus)>,
+    mut sites: Query<&mut ConstructionSite>,
+) {
+    let leveling_crew = jobs.iter().filter(|job| job.worker == Some(Worker::Crew)).count();
+    let building_crew = sites.iter().filter(|site| site.worker == Some(Worker::Crew)).count();
+    let maintenance_crew = budget.crew(buildings.iter().len());
+    let mut free_crew = workforce.crew.saturating_sub((leveling_crew + building_crew) as u32 + maintenance_crew);
+
+    let mut order: Vec<(Entity, u32)> = buildings
+        .iter()
+        .filter(|(_, building, _)| building.operating())
+        .map(|(entity, building, _)| (entity, building.kind.crew()))
+        .collect();
+    order.sort_by_key(|&(entity, _)| entity);
+    let mut staffed = HashSet::new();
+    for (entity, crew) in order {
+        if crew <= free_crew {
+            free_crew -= crew;
+            staffed.insert(entity);
+        }
+    }
+
+    let conductors: HashSet<(u32, u32)> = buildings
+        .iter()
+        .map(|(_, building, _)| (building.x, building.y))
+        .chain(cables.iter().map(|cable| (cable.x, cable.y)))
+        .collect();
+    let network_of = power_networks(&conductors);
+    let mut balance: HashMap<usize, f32> = HashMap::new();
+    for (entity, building, _) in buildings.iter() {
+        if staffed.contains(&entity) {
+            *balance.entry(network_of[&(building.x, building.y)]).or_default() += building.power();
+        }
+    }
+
+    for (entity, building, mut status) in buildings.iter_mut() {
+        let network = network_of[&(building.x, building.y)];
+        let powered = building.operating()
+            && (building.power() >= 0.0 || balance.get(&network).is_some_and(|&net| net >= 0.0));
+        let staffed = staffed.contains(&entity);
+        let output = match building.kind.product() {
+            Some((product, rate)) if powered && staffed => {
+                let helium3 = tile_map.helium3(building.x, building.y).unwrap_or(0.0);
+                let rate = rate * building.level_factor();
+                match product {
+                    Product::Helium3 => rate * helium3 / RATED_HELIUM3_PPB,
+                    Product::Oxygen | Product::Research => rate,
+                }
+            }
+            _ => 0.0,
+        };
+        let next = BuildingStatus { powered, staffed, output };
+        // Only write on change so `Changed<BuildingStatus>` stays meaningful
+        if *status != next {
+            *status = next;
+        }
+    }
+
+    for mut site in sites.iter_mut() {
+        let powered = tile_map
+            .neighbors4(site.building.x, site.building.y)
+            .filter_map(|tile| network_of.get(&tile))
+            .any(|network| balance.get(network).is_some_and(|&net| net >= SITE_POWER));
+        if site.powered != powered {
+            site.powered = powered;
+        }
+    }
+}
+
+/// Put each day's oxygen and helium-3 into the building's own store, up to
+/// its capacity. Water and metals won from the mined regolith go to the
+/// stockpile. Research goes straight to `simulation::accumulate_research`.
+pub fn run_production(
+    mut new_day: EventReader<NewDay>,
+    mut stockpile: ResMut<Stockpile>,
+    mut buildings: Query<(&mut Building, &BuildingStatus)>,
+) {
+    let days = new_day.read().count() as f32;
+    if days == 0.0 {
+        return;
+    }
+    for (mut building, status) in buildings.iter_mut() {
+        let Some((product, _)) = building.kind.product().filter(|_| status.output > 0.0) else {
+            continue;
+        };
+        if product == Product::Helium3 {
+            stockpile.add(Commodity::Water, status.output * WATER_PER_HELIUM3 * days);
+            stockpile.add(Commodity::Metals, status.output * METALS_PER_HELIUM3 * days);
+        }
+        if product != Product::Research {
+            building.stored = (building.stored + status.output * days).min(building.storage());
+        }
+    }
+}
+
+/// The crew breathe oxygen from the plants' stores first, then from the
+/// lander's tanks. Anyone left without air is evacuated to Earth.
+pub fn breathe(
+    mut new_day: EventReader<NewDay>,
+    mut life_support: ResMut<LifeSupport>,
+    mut workforce: ResMut<Workforce>,
+    mut buildings: Query<&mut Building>,
+    mut news: EventWriter<News>,
+) {
+    let days = new_day.read().count() as f32;
+    if days == 0.0 || workforce.crew == 0 {
+        return;
+    }
+    let needed = workforce.crew as f32 * OXYGEN_PER_CREW * days;
+    let mut short = needed - market::take_stored(buildings.iter_mut(), Product::Oxygen, needed);
+    let from_tanks = short.min(life_support.lander_reserve);
+    life_support.lander_reserve -= from_tanks;
+    short -= from_tanks;
+    if short > 0.0 {
+        let evacuated = ((short / (OXYGEN_PER_CREW * days)).ceil() as u32).min(workforce.crew);
+        workforce.crew -= evacuated;
+        warn!("Out of oxygen: {} crew evacuated to Earth", evacuated);
+        news.send(News::new(NewsKind::Disaster, format!("Out of oxygen: {} crew evacuated", evacuated)));
+    }
+}
+
+/// Daily running costs of the buildings and the crew's pay and board. The
+/// crew eat imported food while it lasts and rations paid for daily after.
+pub fn pay_upkeep(
+    mut new_day: EventReader<NewDay>,
+    mut treasury: ResMut<Treasury>,
+    mut stockpile: ResMut<Stockpile>,
+    workforce: Res<Workforce>,
+    buildings: Query<&Building>,
+) {
+    let days = new_day.read().count() as f32;
+    if days == 0.0 {
+        return;
+    }
+    treasury.spend(Account::Upkeep, buildings.iter().map(|building| building.upkeep()).sum::<f32>() * days);
+    treasury.spend(Account::Salaries, workforce.crew as f32 * SALARY_PER_CREW * days);
+
+    let needed = workforce.crew as f32 * FOOD_PER_CREW * days;
+    let unfed = needed - stockpile.take(Commodity::Food, needed);
+    treasury.spend(Account::Purchases, unfed / FOOD_PER_CREW * RATION_COST);
+}
+
+/// Tear down buildings, cable segments and construction sites, refund part
+/// of their cost and deal with the fallout: stored goods move to other
+/// buildings with room for them or are lost, crew without a bunk are flown
+/// home, and a cut cable may leave the grid in pieces. The whole batch is
+/// recorded as one undoable action.
+pub fn demolish(
+    mut commands: Commands,
+    mut events: EventReader<Demolish>,
+    mut treasury: ResMut<Treasury>,
+    mut workforce: ResMut<Workforce>,
+    mut history: ResMut<History>,
+    mut news: EventWriter<News>,
+    clock: Res<GameClock>,
+    mut buildings: Query<(Entity, &mut Building)>,
+    cables: Query<(Entity, &PowerCable)>,
+    sites: Query<(Entity, &ConstructionSite)>,
+) {
+    let targets: HashSet<Entity> = events.read().map(|event| event.entity).collect();
+    if targets.is_empty() {
+        return;
+    }
+
+    let housing_before = LANDER_HOUSING + buildings.iter().map(|(_, building)| building.kind.housing()).sum::<u32>();
+    let mut removed_tiles = Vec::new();
+    let mut removed = Vec::new();
+    let mut refund = 0.0;
+    let mut salvage = Vec::new();
+    for (entity, building) in buildings.iter().filter(|(entity, _)| targets.contains(entity)) {
+        refund += building.demolition_refund();
+        removed_tiles.push((building.x, building.y));
+        removed.push((entity, Structure::Building(building.clone())));
+        if let Some((product, _)) = building.kind.product().filter(|_| building.stored > 0.0) {
+            salvage.push((product, building.stored));
+        }
+        info!("Demolished {} at ({}, {})", building.kind.name(), building.x, building.y);
+        commands.entity(entity).despawn();
+    }
+    for (entity, cable) in cables.iter().filter(|(entity, _)| targets.contains(entity)) {
+        refund += cable.cost * DEMOLITION_REFUND;
+        removed_tiles.push((cable.x, cable.y));
+        removed.push((entity, Structure::Cable(cable.clone())));
+        commands.entity(entity).despawn();
+    }
+    for (entity, site) in sites.iter().filter(|(entity, _)| targets.contains(entity)) {
+        refund += site.building.demolition_refund();
+        removed.push((entity, Structure::Site(site.clone())));
+        info!("Cancelled {} construction at ({}, {})", site.kind().name(), site.building.x, site.building.y);
+        commands.entity(entity).despawn();
+    }
+    treasury.earn(Account::Refunds, refund);
+
+    let mut moved_to = Vec::new();
+    for (product, mut amount) in salvage {
+        for (entity, mut building) in buildings.iter_mut().filter(|(entity, _)| !targets.contains(entity)) {
+            if building.kind.product().is_some_and(|(p, _)| p == product) {
+                let moved = amount.min(building.storage() - building.stored);
+                if moved > 0.0 {
+                    building.stored += moved;
+                    amount -= moved;
+                    moved_to.push((entity, moved));
+                }
+            }
+        }
+        if amount > 0.0 {
+            warn!("Lost {:.0} {} with nowhere to store it", amount, product.unit());
+        }
+    }
+
+    let housing = LANDER_HOUSING
+        + buildings
+            .iter()
+            .filter(|(entity, _)| !targets.contains(entity))
+            .map(|(_, building)| building.kind.housing())
+            .sum::<u32>();
+    // Only the quarters torn down here send anyone home; crew already over
+    // the limit for other reasons are left alone
+    let crew_lost = workforce.crew.saturating_sub(housing).min(housing_before.saturating_sub(housing));
+    if crew_lost > 0 {
+        let text = format!("{} crew lost their quarters and are returning to Earth", crew_lost);
+        warn!("{}", text);
+        news.send(News::new(NewsKind::Disaster, text));
+        workforce.crew -= crew_lost;
+    }
+    history.record(clock.day, Action::Demolish { removed, refund, crew_lost, moved: moved_to });
+
+    let remaining: HashSet<(u32, u32)> = buildings
+        .iter()
+        .filter(|(entity, _)| !targets.contains(entity))
+        .map(|(_, building)| (building.x, building.y))
+        .chain(cables.iter().filter(|(entity, _)| !targets.contains(entity)).map(|(_, cable)| (cable.x, cable.y)))
+        .collect();
+    let network_of = power_networks(&remaining);
+    for (x, y) in removed_tiles {
+        let sides: HashSet<usize> = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+            .iter()
+            .filter_map(|tile| network_of.get(tile).copied())
+            .collect();
+        if sides.len() > 1 {
+            let text = format!("Removing ({}, {}) split the power grid into {} networks", x, y, sides.len());
+            warn!("{}", text);
+            news.send(News::new(NewsKind::Disaster, text).at(x, y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+    use crate::mission::TerrainType;
+
+    #[test]
+    fn test_power_networks() {
+        // Two runs of cable with a gap between them
+        let conductors: HashSet<_> = [(0, 0), (1, 0), (2, 0), (4, 0), (5, 0)].into_iter().collect();
+        let networks = power_networks(&conductors);
+        assert_eq!(networks[&(0, 0)], networks[&(2, 0)]);
+        assert_eq!(networks[&(4, 0)], networks[&(5, 0)]);
+        assert_ne!(networks[&(0, 0)], networks[&(4, 0)]);
+    }
+
+    fn run(crew: u32, buildings: &[(BuildingKind, u32)], cables: &[u32]) -> Vec<BuildingStatus> {
+        let mut app = App::new();
+        app.insert_resource(Workforce { crew, bulldozers: 0 })
+            .insert_resource(MaintenanceBudget { share: 0.0 })
+            .insert_resource(TileMap::new(16, 1))
+            .add_systems(Update, update_building_status);
+        let entities: Vec<Entity> = buildings
+            .iter()
+            .map(|&(kind, x)| app.world.spawn((Building::new(kind, x, 0, 0.0), BuildingStatus::default())).id())
+            .collect();
+        for &x in cables {
+            app.world.spawn(PowerCable { x, y: 0, cost: 0.0 });
+        }
+        app.update();
+        entities.iter().map(|&entity| *app.world.get::<BuildingStatus>(entity).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_connected_lab_runs() {
+        let status = run(8, &[(BuildingKind::SolarArray, 0), (BuildingKind::ScienceLab, 2)], &[1]);
+        assert!(status[1].powered && status[1].staffed);
+        assert_eq!(status[1].output, 5.0);
+    }
+
+    #[test]
+    fn test_unconnected_lab_is_unpowered() {
+        let status = run(8, &[(BuildingKind::SolarArray, 0), (BuildingKind::ScienceLab, 3)], &[1]);
+        assert!(!status[1].powered);
+        assert_eq!(status[1].output, 0.0);
+    }
+
+    #[test]
+    fn test_overloaded_network_browns_out() {
+        // 100 kW of solar against two 60 kW mines
+        let status = run(
+            8,
+            &[(BuildingKind::SolarArray, 0), (BuildingKind::HeliumMine, 1), (BuildingKind::HeliumMine, 2)],
+            &[],
+        );
+        assert!(!status[1].powered && !status[2].powered);
+    }
+
+    #[test]
+    fn test_crew_runs_out() {
+        // Two labs need six crew, and only five are on hand
+        let status = run(
+            5,
+            &[(BuildingKind::SolarArray, 0), (BuildingKind::ScienceLab, 1), (BuildingKind::ScienceLab, 2)],
+            &[],
+        );
+        assert!(status[1].staffed);
+        assert!(!status[2].staffed);
+        assert_eq!(status[2].output, 0.0);
+        // The unstaffed lab draws nothing, so the staffed one keeps its power
+        assert!(status[1].powered);
+    }
+
+    #[test]
+    fn test_site_powered_from_adjacent_network() {
+        let mut app = App::new();
+        app.insert_resource(Workforce { crew: 8, bulldozers: 0 })
+            .insert_resource(MaintenanceBudget { share: 0.0 })
+            .insert_resource(TileMap::new(8, 1))
+            .add_systems(Update, update_building_status);
+        app.world.spawn((Building::new(BuildingKind::SolarArray, 0, 0, 0.0), BuildingStatus::default()));
+        let site = |x| ConstructionSite::new(Building::new(BuildingKind::ScienceLab, x, 0, 0.0), TerrainType::Flat, 0.0);
+        let near = app.world.spawn(site(1)).id();
+        let far = app.world.spawn(site(3)).id();
+        app.update();
+
+        assert!(app.world.get::<ConstructionSite>(near).unwrap().powered);
+        assert!(!app.world.get::<ConstructionSite>(far).unwrap().powered);
+    }
+
+    #[test]
+    fn test_maintenance_rounds_take_crew() {
+        let mut app = App::new();
+        app.insert_resource(Workforce { crew: 3, bulldozers: 0 })
+            .insert_resource(MaintenanceBudget { share: 1.0 })
+            .insert_resource(TileMap::new(4, 1))
+            .add_systems(Update, update_building_status);
+        app.world.spawn((Building::new(BuildingKind::SolarArray, 0, 0, 0.0), BuildingStatus::default()));
+        let lab = app.world.spawn((Building::new(BuildingKind::ScienceLab, 1, 0, 0.0), BuildingStatus::default())).id();
+        app.update();
+
+        // One of the three crew is on maintenance, leaving too few for the lab
+        assert!(!app.world.get::<BuildingStatus>(lab).unwrap().staffed);
+    }
+
+    #[test]
+    fn test_broken_building_stops() {
+        let mut app = App::new();
+        app.insert_resource(Workforce { crew: 8, bulldozers: 0 })
+            .insert_resource(MaintenanceBudget { share: 0.0 })
+            .insert_resource(TileMap::new(4, 1))
+            .add_systems(Update, update_building_status);
+        app.world.spawn((Building::new(BuildingKind::SolarArray, 0, 0, 0.0), BuildingStatus::default()));
+        let mut lab = Building::new(BuildingKind::ScienceLab, 1, 0, 0.0);
+        lab.broken = true;
+        let lab = app.world.spawn((lab, BuildingStatus::default())).id();
+        app.update();
+
+        assert_eq!(*app.world.get::<BuildingStatus>(lab).unwrap(), BuildingStatus::default());
+    }
+
+    #[test]
+    fn test_production_fills_storage() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(Treasury::new(1000.0))
+            .insert_resource(Workforce { crew: 2, bulldozers: 0 })
+            .init_resource::<Stockpile>()
+            .add_systems(Update, (run_production, pay_upkeep));
+        let running = BuildingStatus { powered: true, staffed: true, output: 300.0 };
+        let plant = app.world.spawn((Building::new(BuildingKind::OxygenPlant, 0, 0, 0.0), running)).id();
+        let mining = BuildingStatus { output: 10.0, ..running };
+        app.world.spawn((Building::new(BuildingKind::HeliumMine, 1, 0, 0.0), mining));
+
+        for _ in 0..2 {
+            app.world.send_event(NewDay);
+            app.update();
+        }
+        let plant = app.world.get::<Building>(plant).unwrap();
+        assert_eq!(plant.stored, plant.storage());
+        assert_eq!(app.world.resource::<Stockpile>().get(Commodity::Metals), 20.0 * METALS_PER_HELIUM3);
+        let upkeep = plant.upkeep() + Building::new(BuildingKind::HeliumMine, 1, 0, 0.0).upkeep();
+        let salaries = 2.0 * (SALARY_PER_CREW + RATION_COST);
+        assert_eq!(app.world.resource::<Treasury>().balance(), 1000.0 - 2.0 * (upkeep + salaries));
+    }
+
+    #[test]
+    fn test_crew_eat_imported_food() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .insert_resource(Treasury::new(1000.0))
+            .insert_resource(Workforce { crew: 5, bulldozers: 0 })
+            .init_resource::<Stockpile>()
+            .add_systems(Update, pay_upkeep);
+        app.world.resource_mut::<Stockpile>().add(Commodity::Food, 3.0 * FOOD_PER_CREW);
+
+        // Three are fed from the stockpile; the other two need rations
+        app.world.send_event(NewDay);
+        app.update();
+        assert_eq!(app.world.resource::<Stockpile>().get(Commodity::Food), 0.0);
+        let balance = app.world.resource::<Treasury>().balance();
+        assert!((balance - (1000.0 - 5.0 * SALARY_PER_CREW - 2.0 * RATION_COST)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_switched_off_building_idles() {
+        let mut app = App::new();
+        app.insert_resource(Workforce { crew: 8, bulldozers: 0 })
+            .insert_resource(MaintenanceBudget { share: 0.0 })
+            .insert_resource(TileMap::new(4, 1))
+            .add_systems(Update, update_building_status);
+        app.world.spawn((Building::new(BuildingKind::SolarArray, 0, 0, 0.0), BuildingStatus::default()));
+        let mut lab = Building::new(BuildingKind::ScienceLab, 1, 0, 0.0);
+        lab.enabled = false;
+        let lab = app.world.spawn((lab, BuildingStatus::default())).id();
+        app.update();
+
+        assert_eq!(*app.world.get::<BuildingStatus>(lab).unwrap(), BuildingStatus::default());
+    }
+
+    fn demolition_app(crew: u32) -> App {
+        let mut app = App::new();
+        app.add_event::<Demolish>()
+            .add_event::<News>()
+            .insert_resource(Treasury::new(0.0))
+            .insert_resource(Workforce { crew, bulldozers: 0 })
+            .init_resource::<History>()
+            .init_resource::<GameClock>()
+            .add_systems(Update, demolish);
+        app
+    }
+
+    #[test]
+    fn test_demolish_refunds() {
+        let mut app = demolition_app(0);
+        let plant = app.world.spawn(Building::new(BuildingKind::OxygenPlant, 1, 1, 80_000.0)).id();
+        let cable = app.world.spawn(PowerCable { x: 2, y: 1, cost: 1_000.0 }).id();
+
+        app.world.send_event(Demolish { entity: plant });
+        app.world.send_event(Demolish { entity: cable });
+        app.update();
+        assert!(app.world.get_entity(plant).is_none());
+        assert!(app.world.get_entity(cable).is_none());
+        assert_eq!(app.world.resource::<Treasury>().balance(), (80_000.0 + 1_000.0) * DEMOLITION_REFUND);
+    }
+
+    #[test]
+    fn test_demolish_moves_stored_goods() {
+        let mut app = demolition_app(0);
+        let mut full = Building::new(BuildingKind::OxygenPlant, 0, 0, 0.0);
+        full.stored = 400.0;
+        let full = app.world.spawn(full).id();
+        let mut other = Building::new(BuildingKind::OxygenPlant, 5, 5, 0.0);
+        other.stored = 300.0;
+        let other = app.world.spawn(other).id();
+
+        app.world.send_event(Demolish { entity: full });
+        app.update();
+        // Only 200 kg fit in the other plant; the rest is lost
+        assert_eq!(app.world.get::<Building>(other).unwrap().stored, 500.0);
+    }
+
+    #[test]
+    fn test_demolish_housing_sends_crew_home() {
+        let mut app = demolition_app(LANDER_HOUSING + 4);
+        let module = app.world.spawn(Building::new(BuildingKind::LivingModule, 0, 0, 0.0)).id();
+
+        app.world.send_event(Demolish { entity: module });
+        app.update();
+        assert_eq!(app.world.resource::<Workforce>().crew, LANDER_HOUSING);
+    }
+
+    #[test]
+    fn test_demolish_keeps_crew_when_housing_is_unchanged() {
+        // Already over the limit, but a cable takes no quarters with it
+        let mut app = demolition_app(LANDER_HOUSING + 4);
+        let cable = app.world.spawn(PowerCable { x: 0, y: 0, cost: 0.0 }).id();
+
+        app.world.send_event(Demolish { entity: cable });
+        app.update();
+        assert_eq!(app.world.resource::<Workforce>().crew, LANDER_HOUSING + 4);
+    }
+
+    #[test]
+    fn test_demolish_reports_split_grid() {
+        let mut app = demolition_app(0);
+        app.world.spawn(Building::new(BuildingKind::SolarArray, 0, 0, 0.0));
+        let middle = app.world.spawn(PowerCable { x: 1, y: 0, cost: 0.0 }).id();
+        app.world.spawn(Building::new(BuildingKind::ScienceLab, 2, 0, 0.0));
+
+        app.world.send_event(Demolish { entity: middle });
+        app.update();
+        let news: Vec<News> = app.world.resource_mut::<Events<News>>().drain().collect();
+        assert_eq!(news.len(), 1);
+        assert_eq!(news[0].location, Some((1, 0)));
+    }
+
+    #[test]
+    fn test_crew_breathe_stores_then_tanks() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .add_event::<News>()
+            .insert_resource(Workforce { crew: 10, bulldozers: 0 })
+            .insert_resource(LifeSupport { lander_reserve: 15.0 })
+            .add_systems(Update, breathe);
+        let mut plant = Building::new(BuildingKind::OxygenPlant, 0, 0, 0.0);
+        plant.stored = 6.0;
+        let plant = app.world.spawn(plant).id();
+
+        app.world.send_event(NewDay);
+        app.update();
+        assert_eq!(app.world.get::<Building>(plant).unwrap().stored, 0.0);
+        assert_eq!(app.world.resource::<LifeSupport>().lander_reserve, 11.0);
+
+        // 11 kg for 10 crew over two days is 9 kg short: five have to go home
+        app.world.send_event(NewDay);
+        app.world.send_event(NewDay);
+        app.update();
+        assert_eq!(app.world.resource::<LifeSupport>().lander_reserve, 0.0);
+        assert_eq!(app.world.resource::<Workforce>().crew, 5);
+    }
+}
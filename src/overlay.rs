@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::buildings::{Building, BuildingStatus, PowerCable, Product, RATED_HELIUM3_PPB};
+use crate::map::TileMap;
+use crate::mission::MapDisplay;
+use crate::operations::power_networks;
+
+/// Tiles around a working oxygen plant that its pipes can supply
+pub const OXYGEN_RANGE: u32 = 8;
+
+/// How strongly an overlay tints the terrain under it
+const TINT: f32 = 0.5;
+
+/// Data the main map can be tinted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overlay {
+    #[default]
+    None,
+    Power,
+    Deposits,
+    Slope,
+    Oxygen,
+}
+
+impl Overlay {
+    pub const ALL: [Overlay; 5] = [Overlay::None, Overlay::Power, Overlay::Deposits, Overlay::Slope, Overlay::Oxygen];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Overlay::None => "None",
+            Overlay::Power => "Power coverage",
+            Overlay::Deposits => "Helium-3 deposits",
+            Overlay::Slope => "Slope",
+            Overlay::Oxygen => "Oxygen coverage",
+        }
+    }
+}
+
+/// Tiles reached by a working power grid and by oxygen supply
+#[derive(Resource, Default, PartialEq)]
+pub struct Coverage {
+    pub power: HashSet<(u32, u32)>,
+    pub oxygen: HashSet<(u32, u32)>,
+}
+
+impl Coverage {
+    /// A powered network covers its own tiles and the ones next to them,
+    /// where a new building would connect. Oxygen reaches `OXYGEN_RANGE`
+    /// tiles from each plant that is producing.
+    pub fn survey<'a>(
+        tile_map: &TileMap,
+        buildings: &[(&Building, &BuildingStatus)],
+        cables: impl Iterator<Item = &'a PowerCable>,
+    ) -> Self {
+        let conductors: HashSet<(u32, u32)> = buildings
+            .iter()
+            .map(|(building, _)| (building.x, building.y))
+            .chain(cables.map(|cable| (cable.x, cable.y)))
+            .collect();
+        let network_of = power_networks(&conductors);
+        let mut supply: HashMap<usize, (f32, f32)> = HashMap::new();
+        for (building, _) in buildings.iter().filter(|(_, status)| status.staffed) {
+            let (generated, balance) = supply.entry(network_of[&(building.x, building.y)]).or_default();
+            *generated += building.power().max(0.0);
+            *balance += building.power();
+        }
+        let live = |network: &usize| {
+            supply.get(network).is_some_and(|&(generated, balance)| generated > 0.0 && balance >= 0.0)
+        };
+
+        let mut coverage = Coverage::default();
+        for (&(x, y), network) in network_of.iter() {
+            if live(network) {
+                coverage.power.insert((x, y));
+                coverage.power.extend(tile_map.neighbors4(x, y));
+            }
+        }
+        let plants = buildings.iter().filter(|(building, status)| {
+            status.output > 0.0 && building.kind.product().is_some_and(|(product, _)| product == Product::Oxygen)
+        });
+        for (plant, _) in plants {
+            let reach = OXYGEN_RANGE as i64;
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let (x, y) = (plant.x as i64 + dx, plant.y as i64 + dy);
+                    let inside = x >= 0 && y >= 0 && tile_map.contains(x as u32, y as u32);
+                    if inside && dx * dx + dy * dy <= reach * reach {
+                        coverage.oxygen.insert((x as u32, y as u32));
+                    }
+                }
+            }
+        }
+        coverage
+    }
+}
+
+/// Colour an overlay lays over a tile, with how much of it shows
+pub fn tint(overlay: Overlay, tile_map: &TileMap, coverage: &Coverage, x: u32, y: u32) -> Option<(Color, f32)> {
+    match overlay {
+        Overlay::None => None,
+        Overlay::Power => coverage.power.contains(&(x, y)).then_some((Color::YELLOW, TINT)),
+        Overlay::Oxygen => coverage.oxygen.contains(&(x, y)).then_some((Color::CYAN, TINT)),
+        Overlay::Deposits => {
+            let grade = tile_map.helium3(x, y)? / (RATED_HELIUM3_PPB * 1.5);
+            (grade > 0.05).then_some((Color::ORANGE, TINT * grade.min(1.0)))
+        }
+        Overlay::Slope => {
+            let (_, slope) = tile_map.surface(x, y)?;
+            let steep = (slope / 0.5).min(1.0);
+            Some((Color::rgb(steep, 1.0 - steep, 0.0), TINT))
+        }
+    }
+}
+
+/// Blend `color` towards `over` by `amount`
+pub fn blend(color: Color, (over, amount): (Color, f32)) -> Color {
+    Color::rgb(
+        color.r() + (over.r() - color.r()) * amount,
+        color.g() + (over.g() - color.g()) * amount,
+        color.b() + (over.b() - color.b()) * amount,
+    )
+}
+
+/// Keep the coverage up to date while an overlay that shows it is on. The
+/// resource is only written when something changed, so the map is only
+/// re-tinted then.
+pub fn update_coverage(
+    display: Res<MapDisplay>,
+    tile_map: Option<Res<TileMap>>,
+    mut coverage: ResMut<Coverage>,
+    buildings: Query<(&Building, &BuildingStatus)>,
+    cables: Query<&PowerCable>,
+) {
+    let Some(tile_map) = tile_map.filter(|_| matches!(display.overlay, Overlay::Power | Overlay::Oxygen)) else {
+        return;
+    };
+    let buildings: Vec<_> = buildings.iter().collect();
+    let next = Coverage::survey(&tile_map, &buildings, cables.iter());
+    if *coverage != next {
+        *coverage = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+
+    #[test]
+    fn test_power_coverage() {
+        let map = TileMap::new(10, 3);
+        let staffed = BuildingStatus { powered: true, staffed: true, output: 0.0 };
+        let solar = Building::new(BuildingKind::SolarArray, 0, 1, 0.0);
+        let lab = Building::new(BuildingKind::ScienceLab, 2, 1, 0.0);
+        // A lone module with no generator covers nothing
+        let module = Building::new(BuildingKind::LivingModule, 8, 1, 0.0);
+        let buildings = [(&solar, &staffed), (&lab, &staffed), (&module, &staffed)];
+        let cable = PowerCable { x: 1, y: 1, cost: 0.0 };
+
+        let coverage = Coverage::survey(&map, &buildings, [&cable].into_iter());
+        assert!(coverage.power.contains(&(1, 1)) && coverage.power.contains(&(3, 1)));
+        assert!(coverage.power.contains(&(2, 0)));
+        assert!(!coverage.power.contains(&(4, 1)) && !coverage.power.contains(&(8, 1)));
+    }
+
+    #[test]
+    fn test_oxygen_coverage() {
+        let map = TileMap::new(30, 30);
+        let producing = BuildingStatus { powered: true, staffed: true, output: 50.0 };
+        let plant = Building::new(BuildingKind::OxygenPlant, 10, 10, 0.0);
+        let coverage = Coverage::survey(&map, &[(&plant, &producing)], std::iter::empty());
+        assert!(coverage.oxygen.contains(&(10 + OXYGEN_RANGE, 10)));
+        assert!(!coverage.oxygen.contains(&(10 + OXYGEN_RANGE, 10 + OXYGEN_RANGE)));
+        assert!(tint(Overlay::Oxygen, &map, &coverage, 10, 10).is_some());
+        assert!(tint(Overlay::None, &map, &coverage, 10, 10).is_none());
+    }
+}
@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::buildings::{self, Building, PowerCable};
+use crate::construction::{self, ConstructionSite};
+use crate::events::EventLog;
+use crate::finance::Treasury;
+use crate::funding::Funding;
+use crate::leveling::LevelingJob;
+use crate::logistics::Shipping;
+use crate::maintenance::{MaintenanceBudget, SolarFlare};
+use crate::map::TileMap;
+use crate::market::{Market, Stockpile};
+use crate::mission::{CompletedObjectives, MapTile, Mission, MissionFailed};
+use crate::simulation::{GameClock, GameRng, LifeSupport, Research, Workforce};
+
+pub const SAVE_FILE: &str = "savegame.json";
+
+/// Everything needed to restore a game. The map itself is not stored: it is
+/// regenerated from the mission and seed, and the tiles changed since are
+/// laid over it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SaveGame {
+    pub mission_id: usize,
+    pub seed: u64,
+    /// Saves from before map sizes were selectable are 64x64
+    #[serde(default = "default_map_size")]
+    pub map_size: (u32, u32),
+    /// The base and economy. Saves from before these were stored only
+    /// restart the mission.
+    #[serde(default)]
+    pub progress: Option<Progress>,
+}
+
+fn default_map_size() -> (u32, u32) {
+    (64, 64)
+}
+
+/// The state of a mission under way.
+///
+/// Undo history and the news ticker are not kept. The random number stream
+/// can't be stored either, so a loaded game reseeds it from the mission seed
+/// and the day.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Progress {
+    pub tiles: Vec<MapTile>,
+    pub buildings: Vec<Building>,
+    pub cables: Vec<PowerCable>,
+    pub sites: Vec<ConstructionSite>,
+    pub leveling: Vec<LevelingJob>,
+    pub treasury: Treasury,
+    pub workforce: Workforce,
+    pub clock: GameClock,
+    pub research: Research,
+    pub maintenance: MaintenanceBudget,
+    pub market: Market,
+    pub stockpile: Stockpile,
+    pub shipping: Shipping,
+    pub funding: Funding,
+    pub event_log: EventLog,
+    pub objectives: CompletedObjectives,
+    pub life_support: LifeSupport,
+    pub solar_flare: Option<SolarFlare>,
+    pub failed: Option<MissionFailed>,
+}
+
+impl Progress {
+    /// Read the running mission out of the world, or `None` if there isn't one
+    pub fn capture(world: &mut World) -> Option<Self> {
+        fn cloned<T: Resource + Clone>(world: &World) -> Option<T> {
+            world.get_resource::<T>().cloned()
+        }
+        let tiles = world.get_resource::<TileMap>()?.edited_tiles();
+        Some(Self {
+            tiles,
+            buildings: world.query::<&Building>().iter(world).cloned().collect(),
+            cables: world.query::<&PowerCable>().iter(world).cloned().collect(),
+            sites: world.query::<&ConstructionSite>().iter(world).cloned().collect(),
+            leveling: world.query::<&LevelingJob>().iter(world).cloned().collect(),
+            treasury: cloned(world)?,
+            workforce: cloned(world)?,
+            clock: cloned(world)?,
+            research: cloned(world)?,
+            maintenance: cloned(world)?,
+            market: cloned(world)?,
+            stockpile: cloned(world)?,
+            shipping: cloned(world)?,
+            funding: cloned(world)?,
+            event_log: cloned(world)?,
+            objectives: cloned(world)?,
+            life_support: cloned(world)?,
+            solar_flare: cloned(world),
+            failed: cloned(world),
+        })
+    }
+
+    /// Put a freshly started mission back the way it was saved, replacing
+    /// its starting resources
+    pub fn restore(self, commands: &mut Commands, tile_map: &mut TileMap, seed: u64) {
+        for tile in &self.tiles {
+            tile_map.set(tile);
+        }
+        for building in self.buildings {
+            buildings::spawn_building(commands, building);
+        }
+        for cable in self.cables {
+            buildings::spawn_cable(commands, cable);
+        }
+        for site in self.sites {
+            construction::spawn_site(commands, site);
+        }
+        for job in self.leveling {
+            commands.spawn(job);
+        }
+        commands.insert_resource(GameRng::new(seed ^ self.clock.day as u64));
+        commands.insert_resource(self.treasury);
+        commands.insert_resource(self.workforce);
+        commands.insert_resource(self.clock);
+        commands.insert_resource(self.research);
+        commands.insert_resource(self.maintenance);
+        commands.insert_resource(self.market);
+        commands.insert_resource(self.stockpile);
+        commands.insert_resource(self.shipping);
+        commands.insert_resource(self.funding);
+        commands.insert_resource(self.event_log);
+        commands.insert_resource(self.objectives);
+        commands.insert_resource(self.life_support);
+        if let Some(flare) = self.solar_flare {
+            commands.insert_resource(flare);
+        }
+        if let Some(failed) = self.failed {
+            commands.insert_resource(failed);
+        }
+    }
+}
+
+impl SaveGame {
+    /// A save that starts the mission afresh
+    pub fn from_mission(mission: &Mission) -> Self {
+        Self {
+            mission_id: mission.id,
+            seed: mission.seed,
+            map_size: mission.map_size,
+            progress: None,
+        }
+    }
+
+    /// The running mission and everything built and earned in it
+    pub fn capture(world: &mut World) -> Option<Self> {
+        let save = Self::from_mission(world.get_resource::<Mission>()?);
+        Some(Self { progress: Some(Progress::capture(world)?), ..save })
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        std::fs::write(path, json).map_err(|err| err.to_string())
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        serde_json::from_str(&json).map_err(|err| err.to_string())
+    }
+}
+
+/// Ask for the running game to be written to a file; see `write_saves`
+#[derive(Event)]
+pub struct SaveRequest {
+    pub path: String,
+}
+
+/// A save being loaded, restored by `mission::setup_mission` once the
+/// mission it belongs to has started
+#[derive(Resource)]
+pub struct PendingLoad(pub Progress);
+
+/// Write the game out for each `SaveRequest`. This needs the whole world, so
+/// it runs as an exclusive system.
+pub fn write_saves(world: &mut World) {
+    let requests: Vec<SaveRequest> = world.resource_mut::<Events<SaveRequest>>().drain().collect();
+    for request in requests {
+        let Some(save) = SaveGame::capture(world) else {
+            warn!("Could not save game: no mission is running");
+            continue;
+        };
+        match save.write(&request.path) {
+            Ok(()) => info!("Saved game to {}", request.path),
+            Err(err) => warn!("Could not save game: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+    use crate::finance::Account;
+    use crate::mission::{start_mission, TerrainType};
+
+    fn started_world(mission: Mission, progress: Option<Progress>) -> World {
+        let mut world = World::new();
+        let mut queue = bevy::ecs::system::CommandQueue::default();
+        start_mission(&mut Commands::new(&mut queue, &world), mission, progress);
+        queue.apply(&mut world);
+        world
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let mission = Mission::load(1).unwrap().with_seed(1234).with_map_size((1024, 1024));
+        let save = SaveGame::from_mission(&mission);
+        assert_eq!(save.mission_id, 1);
+        assert_eq!(save.seed, 1234);
+        assert_eq!(save.map_size, (1024, 1024));
+
+        let path = std::env::temp_dir().join("moonbase_test_save_round_trip.json");
+        save.write(&path).unwrap();
+        let read = SaveGame::read(&path).unwrap();
+        assert_eq!((read.mission_id, read.seed, read.map_size), (1, 1234, (1024, 1024)));
+        assert!(read.progress.is_none());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_progress_survives_a_save() {
+        let mission = Mission::load(1).unwrap().with_seed(99);
+        let mut world = started_world(mission.clone(), None);
+        world.spawn(Building::new(BuildingKind::SolarArray, 3, 4, 50_000.0));
+        world.spawn(PowerCable { x: 4, y: 4, cost: 500.0 });
+        world.resource_mut::<Treasury>().spend(Account::Construction, 50_500.0);
+        world.resource_mut::<GameClock>().day = 12;
+        world.resource_mut::<Workforce>().crew = 11;
+        let crater = MapTile { x: 10, y: 10, terrain: TerrainType::Crater, elevation: -6.0, slope: 0.2, helium3: 0.0 };
+        world.resource_mut::<TileMap>().set(&crater);
+
+        let json = serde_json::to_string(&SaveGame::capture(&mut world).unwrap()).unwrap();
+        let save: SaveGame = serde_json::from_str(&json).unwrap();
+        let mut world = started_world(mission, save.progress);
+
+        let balance = world.resource::<Treasury>().balance();
+        assert_eq!(balance, 1_000_000.0 - 50_500.0);
+        assert_eq!(world.resource::<GameClock>().day, 12);
+        assert_eq!(world.resource::<Workforce>().crew, 11);
+        assert_eq!(world.resource::<TileMap>().tile(10, 10), Some(crater));
+        let building = world.query::<&Building>().single(&world);
+        assert_eq!((building.kind, building.x, building.y), (BuildingKind::SolarArray, 3, 4));
+        assert_eq!(world.query::<&PowerCable>().iter(&world).count(), 1);
+    }
+
+    #[test]
+    fn test_old_saves_default_to_small_maps() {
+        let save: SaveGame = serde_json::from_str(r#"{"mission_id": 1, "seed": 5}"#).unwrap();
+        assert_eq!(save.map_size, (64, 64));
+        assert!(save.progress.is_none());
+    }
+
+    #[test]
+    fn test_read_missing_save() {
+        assert!(SaveGame::read("does_not_exist.json").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::{Building, BuildingStatus, Product};
+use crate::market::{Commodity, Stockpile};
+
+/// Real-time seconds per game day at normal speed
+pub const SECONDS_PER_DAY: f32 = 4.0;
+
+/// Research points needed before crater filling is available
+pub const CRATER_FILLING_POINTS: f32 = 100.0;
+
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct GameClock {
+    pub day: u32,
+    pub elapsed: f32,
+}
+
+/// Sent once for every game day that passes
+#[derive(Event)]
+pub struct NewDay;
+
+/// Bunks in the landing craft, there before any Living Module is built
+pub const LANDER_HOUSING: u32 = 8;
+
+/// Kilograms of oxygen in the landing craft's tanks at the start
+pub const LANDER_OXYGEN: f32 = 240.0;
+
+/// Kilograms of oxygen each crew member breathes per day
+pub const OXYGEN_PER_CREW: f32 = 1.0;
+
+/// Tonnes of imported food each crew member eats per day
+pub const FOOD_PER_CREW: f32 = 0.002;
+
+/// Oxygen left in the lander's tanks, drawn on once the plants' stores run dry
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct LifeSupport {
+    pub lander_reserve: f32,
+}
+
+impl Default for LifeSupport {
+    fn default() -> Self {
+        Self { lander_reserve: LANDER_OXYGEN }
+    }
+}
+
+/// Days until the oxygen runs out at today's production, or `None` if the
+/// plants keep up with the crew
+pub fn oxygen_days_left(reserve: f32, production: f32, crew: u32) -> Option<f32> {
+    let deficit = crew as f32 * OXYGEN_PER_CREW - production;
+    (deficit > 0.0).then(|| reserve / deficit)
+}
+
+/// People and vehicles available for field work
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Workforce {
+    pub crew: u32,
+    pub bulldozers: u32,
+}
+
+/// Source of every random roll in the simulation, seeded from the mission
+/// so that a seed replays the same events
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Research {
+    pub points: f32,
+    pub crater_filling: bool,
+}
+
+pub fn advance_clock(
+    time: Res<Time>,
+    mut clock: ResMut<GameClock>,
+    mut new_day: EventWriter<NewDay>,
+) {
+    clock.elapsed += time.delta_seconds();
+    while clock.elapsed >= SECONDS_PER_DAY {
+        clock.elapsed -= SECONDS_PER_DAY;
+        clock.day += 1;
+        new_day.send(NewDay);
+    }
+}
+
+/// Add the labs' and telescopes' daily output to research progress, and to
+/// the stock of data that can be sold on Earth
+pub fn accumulate_research(
+    mut new_day: EventReader<NewDay>,
+    mut research: ResMut<Research>,
+    mut stockpile: ResMut<Stockpile>,
+    buildings: Query<(&Building, &BuildingStatus)>,
+) {
+    let daily: f32 = buildings
+        .iter()
+        .filter(|(building, _)| building.kind.product().is_some_and(|(product, _)| product == Product::Research))
+        .map(|(_, status)| status.output)
+        .sum();
+    for _ in new_day.read() {
+        research.points += daily;
+        stockpile.add(Commodity::ResearchData, daily);
+        if !research.crater_filling && research.points >= CRATER_FILLING_POINTS {
+            research.crater_filling = true;
+            info!("Research complete: crater filling is now available");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buildings::BuildingKind;
+    use std::time::Duration;
+
+    #[test]
+    fn test_oxygen_days_left() {
+        assert_eq!(oxygen_days_left(100.0, 0.0, 10), Some(10.0));
+        assert_eq!(oxygen_days_left(100.0, 5.0, 10), Some(20.0));
+        assert_eq!(oxygen_days_left(100.0, 10.0, 10), None);
+    }
+
+    #[test]
+    fn test_advance_clock() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .init_resource::<GameClock>()
+            .add_systems(Update, advance_clock);
+
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(SECONDS_PER_DAY * 2.5));
+        app.world.insert_resource(time);
+        app.update();
+
+        let clock = app.world.resource::<GameClock>();
+        assert_eq!(clock.day, 2);
+        assert!((clock.elapsed - SECONDS_PER_DAY * 0.5).abs() < 1e-3);
+        assert_eq!(app.world.resource::<Events<NewDay>>().len(), 2);
+    }
+
+    #[test]
+    fn test_research_unlocks_crater_filling() {
+        let mut app = App::new();
+        app.add_event::<NewDay>()
+            .init_resource::<Research>()
+            .init_resource::<Stockpile>()
+            .add_systems(Update, accumulate_research);
+        let running = BuildingStatus { powered: true, staffed: true, output: 5.0 };
+        for x in 0..2 {
+            app.world.spawn((Building::new(BuildingKind::ScienceLab, x, 0, 0.0), running));
+        }
+        // A lab without power contributes nothing
+        app.world.spawn((Building::new(BuildingKind::ScienceLab, 2, 0, 0.0), BuildingStatus::default()));
+
+        let days = (CRATER_FILLING_POINTS / 10.0).ceil() as u32;
+        for _ in 0..days - 1 {
+            app.world.send_event(NewDay);
+            app.update();
+        }
+        assert!(!app.world.resource::<Research>().crater_filling);
+        app.world.send_event(NewDay);
+        app.update();
+        assert!(app.world.resource::<Research>().crater_filling);
+    }
+}
@@ -0,0 +1,364 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::mission::TerrainType;
+
+/// Share of the map covered by each terrain type; flat ground is whatever is left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainMix {
+    pub rough: f32,
+    pub crater: f32,
+    pub mountain: f32,
+    /// Share of the map that is smooth mare plain, where ridges and rubble are rare
+    pub mare: f32,
+}
+
+impl Default for TerrainMix {
+    fn default() -> Self {
+        Self {
+            rough: 0.15,
+            crater: 0.10,
+            mountain: 0.05,
+            mare: 0.30,
+        }
+    }
+}
+
+/// Smooth 2D value noise over a seeded integer lattice
+struct ValueNoise {
+    seed: u64,
+}
+
+impl ValueNoise {
+    fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    fn lattice(&self, x: i32, y: i32) -> f32 {
+        // splitmix64 finaliser over the seeded lattice coordinates
+        let mut z = self.seed
+            ^ (x as i64 as u64).wrapping_mul(0x9E3779B97F4A7C15)
+            ^ (y as i64 as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (ix, iy) = (x0 as i32, y0 as i32);
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+
+        let top = self.lattice(ix, iy) * (1.0 - tx) + self.lattice(ix + 1, iy) * tx;
+        let bottom = self.lattice(ix, iy + 1) * (1.0 - tx) + self.lattice(ix + 1, iy + 1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    /// Fractal noise in 0..1; `scale` is the size in tiles of the largest features
+    fn fbm(&self, x: f32, y: f32, scale: f32, octaves: u32) -> f32 {
+        let (mut total, mut norm, mut amplitude, mut frequency) = (0.0, 0.0, 1.0, 1.0 / scale);
+        for _ in 0..octaves {
+            total += self.sample(x * frequency, y * frequency) * amplitude;
+            norm += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / norm
+    }
+}
+
+/// Indices of the `count` highest scoring tiles among those still unassigned
+fn top_scoring(scores: &[f32], free: &[bool], count: usize) -> Vec<usize> {
+    let mut candidates: Vec<usize> = (0..scores.len()).filter(|&i| free[i]).collect();
+    candidates.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    candidates.truncate(count);
+    candidates
+}
+
+/// Generate a lunar landscape of `width` x `height` tiles in row-major order.
+///
+/// Impact craters are stamped first, then mountain ranges are taken from the
+/// highest ridge noise and crater rims, and rough ground from ejecta blankets
+/// and broken highlands. Mare plains suppress both, leaving smooth flats.
+pub fn generate_terrain(width: u32, height: u32, seed: u64, mix: &TerrainMix) -> Vec<TerrainType> {
+    let (w, h) = (width as usize, height as usize);
+    let total = w * h;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut terrain = vec![TerrainType::Flat; total];
+
+    let mare_noise = ValueNoise::new(seed ^ 0x4D41524520);
+    let ridge_noise = ValueNoise::new(seed ^ 0x5249444745);
+    let detail_noise = ValueNoise::new(seed ^ 0x4445544149);
+
+    // Lowland mare plains are the lowest part of a broad noise field
+    let lowland: Vec<f32> = (0..total)
+        .map(|i| 1.0 - mare_noise.fbm((i % w) as f32, (i / w) as f32, 24.0, 3))
+        .collect();
+    let mut is_mare = vec![false; total];
+    for i in top_scoring(&lowland, &vec![true; total], (mix.mare * total as f32).round() as usize) {
+        is_mare[i] = true;
+    }
+
+    // Stamp craters until they cover the requested share of the map
+    let crater_target = (mix.crater * total as f32).round() as usize;
+    let max_radius = (w.min(h) as f32 / 8.0).max(2.0);
+    let mut rim = vec![0.0f32; total];
+    let mut ejecta = vec![0.0f32; total];
+    let mut crater_tiles = 0;
+    let mut attempts = 0;
+    while crater_tiles < crater_target && attempts < total {
+        attempts += 1;
+        // Small impacts are far more common than large ones
+        let radius = 1.0 + (max_radius - 1.0) * rng.gen::<f32>().powi(3);
+        let cx = rng.gen_range(0.0..w as f32);
+        let cy = rng.gen_range(0.0..h as f32);
+        let reach = (radius * 2.5).ceil() as i32;
+
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                if x < 0 || y < 0 || x >= w as i32 || y >= h as i32 {
+                    continue;
+                }
+                let i = y as usize * w + x as usize;
+                let d = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt() / radius;
+
+                if d < 0.85 {
+                    if terrain[i] != TerrainType::Crater && crater_tiles < crater_target {
+                        terrain[i] = TerrainType::Crater;
+                        crater_tiles += 1;
+                    }
+                } else if d < 1.3 {
+                    // Raised rim, taller for bigger impacts
+                    rim[i] = rim[i].max(radius / max_radius);
+                } else if d < 2.5 {
+                    ejecta[i] += 1.0 - (d - 1.3) / 1.2;
+                }
+            }
+        }
+    }
+
+    let mut free: Vec<bool> = terrain.iter().map(|&t| t != TerrainType::Crater).collect();
+
+    // Mountain ranges follow ridged noise, plus the rims of large craters
+    let mountain_score: Vec<f32> = (0..total)
+        .map(|i| {
+            let n = ridge_noise.fbm((i % w) as f32, (i / w) as f32, 16.0, 4);
+            let ridge = 1.0 - (2.0 * n - 1.0).abs();
+            let score = ridge.powi(3) + rim[i];
+            if is_mare[i] { score * 0.3 } else { score }
+        })
+        .collect();
+    for i in top_scoring(&mountain_score, &free, (mix.mountain * total as f32).round() as usize) {
+        terrain[i] = TerrainType::Mountain;
+        free[i] = false;
+    }
+
+    // Rough ground: ejecta around craters and broken highland terrain
+    let rough_score: Vec<f32> = (0..total)
+        .map(|i| {
+            let detail = detail_noise.fbm((i % w) as f32, (i / w) as f32, 6.0, 3);
+            let score = ejecta[i].min(1.5) + detail + mountain_score[i] * 0.5;
+            if is_mare[i] { score * 0.5 } else { score }
+        })
+        .collect();
+    for i in top_scoring(&rough_score, &free, (mix.rough * total as f32).round() as usize) {
+        terrain[i] = TerrainType::Rough;
+    }
+
+    terrain
+}
+
+/// Horizontal size of one tile in metres
+pub const TILE_METERS: f32 = 10.0;
+
+/// Height field in metres to go with a terrain grid.
+///
+/// Broad rolling noise is combined with per-terrain offsets (peaks for
+/// mountains, bowls for craters) and smoothed so slopes run continuously
+/// between features instead of stepping at tile borders.
+pub fn generate_elevation(width: u32, height: u32, seed: u64, terrain: &[TerrainType]) -> Vec<f32> {
+    let (w, h) = (width as usize, height as usize);
+    let base_noise = ValueNoise::new(seed ^ 0x454C4556);
+    let detail_noise = ValueNoise::new(seed ^ 0x4A4954);
+
+    let mut elevation: Vec<f32> = (0..w * h)
+        .map(|i| {
+            let (x, y) = ((i % w) as f32, (i / w) as f32);
+            let base = base_noise.fbm(x, y, 32.0, 4) * 30.0;
+            base + match terrain[i] {
+                TerrainType::Flat => 0.0,
+                TerrainType::Rough => 4.0,
+                TerrainType::Crater => -25.0,
+                TerrainType::Mountain => 40.0 + detail_noise.fbm(x, y, 4.0, 2) * 40.0,
+            }
+        })
+        .collect();
+
+    for _ in 0..2 {
+        elevation = (0..w * h)
+            .map(|i| {
+                let (x, y) = (i % w, i / w);
+                let mut sum = 0.0;
+                let mut n = 0.0;
+                for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+                    for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                        sum += elevation[ny * w + nx];
+                        n += 1.0;
+                    }
+                }
+                sum / n
+            })
+            .collect();
+    }
+
+    // Rubble stays bumpy after smoothing
+    for (i, value) in elevation.iter_mut().enumerate() {
+        if terrain[i] == TerrainType::Rough {
+            *value += (detail_noise.fbm((i % w) as f32, (i / w) as f32, 1.5, 1) - 0.5) * 8.0;
+        }
+    }
+
+    elevation
+}
+
+/// Slope of every tile as a grade (rise over run, 1.0 = 45 degrees),
+/// taken from the steepest of the central differences across the tile
+pub fn slopes(elevation: &[f32], width: u32, height: u32) -> Vec<f32> {
+    (0..width * height)
+        .map(|i| slope_at(i % width, i / width, width, height, |x, y| elevation[(y * width + x) as usize]))
+        .collect()
+}
+
+/// Slope of a single tile, worked out the same way as `slopes`
+pub fn slope_at(x: u32, y: u32, width: u32, height: u32, elevation: impl Fn(u32, u32) -> f32) -> f32 {
+    let (left, right) = (x.saturating_sub(1), (x + 1).min(width - 1));
+    let (down, up) = (y.saturating_sub(1), (y + 1).min(height - 1));
+    let dx = (elevation(right, y) - elevation(left, y)) / ((right - left).max(1) as f32 * TILE_METERS);
+    let dy = (elevation(x, up) - elevation(x, down)) / ((up - down).max(1) as f32 * TILE_METERS);
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// Helium-3 content of the regolith in parts per billion. It collects in
+/// broad patches; highland rock holds little, crater floors a bit more.
+pub fn generate_helium3(width: u32, height: u32, seed: u64, terrain: &[TerrainType]) -> Vec<f32> {
+    let noise = ValueNoise::new(seed ^ 0x484533);
+    let (w, h) = (width as usize, height as usize);
+    (0..w * h)
+        .map(|i| {
+            let patch = noise.fbm((i % w) as f32, (i / w) as f32, 24.0, 3);
+            let factor = match terrain[i] {
+                TerrainType::Mountain => 0.4,
+                TerrainType::Crater => 1.3,
+                TerrainType::Flat | TerrainType::Rough => 1.0,
+            };
+            (5.0 + 40.0 * patch * patch) * factor
+        })
+        .collect()
+}
+
+/// Relative cost of crossing a tile, shared by cable routing and anything
+/// else that moves over the surface. Steeper ground is slower and dearer.
+pub fn travel_cost(terrain: TerrainType, slope: f32) -> Option<f32> {
+    if terrain == TerrainType::Mountain || slope > MAX_TRAVEL_SLOPE {
+        return None;
+    }
+    Some(1.0 + slope * 8.0)
+}
+
+pub const MAX_TRAVEL_SLOPE: f32 = 0.6;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(terrain: &[TerrainType], kind: TerrainType) -> usize {
+        terrain.iter().filter(|&&t| t == kind).count()
+    }
+
+    #[test]
+    fn test_generate_terrain_respects_mix() {
+        let mix = TerrainMix { rough: 0.2, crater: 0.05, mountain: 0.1, mare: 0.5 };
+        let terrain = generate_terrain(64, 64, 7, &mix);
+        let total = 64.0f32 * 64.0;
+
+        assert_eq!(terrain.len(), 64 * 64);
+        assert_eq!(count(&terrain, TerrainType::Crater), (0.05 * total).round() as usize);
+        assert_eq!(count(&terrain, TerrainType::Mountain), (0.1 * total).round() as usize);
+        assert_eq!(count(&terrain, TerrainType::Rough), (0.2 * total).round() as usize);
+    }
+
+    #[test]
+    fn test_generate_terrain_is_deterministic() {
+        let mix = TerrainMix::default();
+        assert_eq!(generate_terrain(32, 32, 99, &mix), generate_terrain(32, 32, 99, &mix));
+        assert_ne!(generate_terrain(32, 32, 99, &mix), generate_terrain(32, 32, 100, &mix));
+    }
+
+    #[test]
+    fn test_slopes() {
+        // A ramp rising 5 m per tile to the east
+        let elevation: Vec<f32> = (0..9).map(|i| (i % 3) as f32 * 5.0).collect();
+        let ramp = slopes(&elevation, 3, 3);
+        assert!(ramp.iter().all(|&s| (s - 0.5).abs() < 1e-5));
+
+        let level = slopes(&[12.0; 16], 4, 4);
+        assert!(level.iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_generate_elevation_follows_terrain() {
+        let terrain = generate_terrain(64, 64, 11, &TerrainMix::default());
+        let elevation = generate_elevation(64, 64, 11, &terrain);
+        let mean = |kind: TerrainType| {
+            let values: Vec<f32> = (0..terrain.len())
+                .filter(|&i| terrain[i] == kind)
+                .map(|i| elevation[i])
+                .collect();
+            values.iter().sum::<f32>() / values.len() as f32
+        };
+        assert!(mean(TerrainType::Mountain) > mean(TerrainType::Flat));
+        assert!(mean(TerrainType::Crater) < mean(TerrainType::Flat));
+    }
+
+    #[test]
+    fn test_generate_helium3() {
+        let terrain = generate_terrain(64, 64, 3, &TerrainMix::default());
+        let helium3 = generate_helium3(64, 64, 3, &terrain);
+        assert_eq!(helium3, generate_helium3(64, 64, 3, &terrain));
+        assert!(helium3.iter().all(|&ppb| ppb > 0.0 && ppb < 60.0));
+
+        let mean = |kind: TerrainType| {
+            let values: Vec<f32> = (0..helium3.len()).filter(|&i| terrain[i] == kind).map(|i| helium3[i]).collect();
+            values.iter().sum::<f32>() / values.len() as f32
+        };
+        assert!(mean(TerrainType::Mountain) < mean(TerrainType::Flat));
+    }
+
+    #[test]
+    fn test_travel_cost() {
+        assert_eq!(travel_cost(TerrainType::Flat, 0.0), Some(1.0));
+        assert!(travel_cost(TerrainType::Rough, 0.3).unwrap() > 1.0);
+        assert_eq!(travel_cost(TerrainType::Mountain, 0.0), None);
+        assert_eq!(travel_cost(TerrainType::Flat, 1.0), None);
+    }
+
+    #[test]
+    fn test_generate_terrain_is_coherent() {
+        // Independent dice rolls with the default mix give ~52% matching neighbours
+        let terrain = generate_terrain(64, 64, 3, &TerrainMix::default());
+        let mut matching = 0;
+        let mut pairs = 0;
+        for y in 0..64 {
+            for x in 0..63 {
+                pairs += 1;
+                if terrain[y * 64 + x] == terrain[y * 64 + x + 1] {
+                    matching += 1;
+                }
+            }
+        }
+        assert!(matching as f32 / pairs as f32 > 0.65);
+    }
+}
@@ -0,0 +1,348 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use std::collections::HashSet;
+
+use crate::map::{TileMap, CHUNK_SIZE};
+use crate::mission::{MapDisplay, TerrainType};
+use crate::overlay::{blend, tint, Coverage};
+
+/// Chunks kept meshed beyond the edge of the view, so panning doesn't show gaps
+const VIEW_MARGIN: u32 = 1;
+
+const CONTOUR_INTERVAL: f32 = 10.0;
+const CONTOUR_WIDTH: f32 = 1.0;
+
+/// One batched mesh covering a `CHUNK_SIZE` square of tiles
+#[derive(Component)]
+pub struct TerrainChunk {
+    pub cx: u32,
+    pub cy: u32,
+}
+
+fn band(tile_map: &TileMap, x: u32, y: u32) -> i32 {
+    (tile_map.elevation(x, y).unwrap_or(0.0) / CONTOUR_INTERVAL).floor() as i32
+}
+
+pub fn tile_color(terrain: TerrainType, elevation: f32, relief: (f32, f32), shading: bool) -> Color {
+    let color = match terrain {
+        TerrainType::Flat => Color::GRAY,
+        TerrainType::Rough => Color::DARK_GRAY,
+        TerrainType::Crater => Color::BLACK,
+        TerrainType::Mountain => Color::WHITE,
+    };
+    if !shading {
+        return color;
+    }
+    // Low ground darker, high ground brighter; craters stay visible on black
+    let light = 0.5 + (elevation - relief.0) / relief.1 * 0.7;
+    Color::rgb(
+        (color.r() * light).max(0.08 * light),
+        (color.g() * light).max(0.08 * light),
+        (color.b() * light).max(0.08 * light),
+    )
+}
+
+/// Lowest elevation and elevation span, for shading
+pub fn relief(tile_map: &TileMap) -> (f32, f32) {
+    let (min, max) = tile_map.elevation_range();
+    (min, (max - min).max(1.0))
+}
+
+/// Build the mesh for one chunk: a coloured quad per tile plus thin quads for
+/// contour lines along edges where the elevation band changes, tinted by the
+/// selected overlay
+pub fn build_chunk_mesh(
+    tile_map: &TileMap,
+    cx: u32,
+    cy: u32,
+    relief: (f32, f32),
+    display: &MapDisplay,
+    coverage: &Coverage,
+) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    let mut push_quad = |min: Vec2, max: Vec2, z: f32, color: Color| {
+        let base = positions.len() as u32;
+        positions.extend([
+            [min.x, min.y, z],
+            [max.x, min.y, z],
+            [max.x, max.y, z],
+            [min.x, max.y, z],
+        ]);
+        colors.extend([color.as_linear_rgba_f32(); 4]);
+        indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    };
+
+    let x_range = cx * CHUNK_SIZE..((cx + 1) * CHUNK_SIZE).min(tile_map.width);
+    let y_range = cy * CHUNK_SIZE..((cy + 1) * CHUNK_SIZE).min(tile_map.height);
+    let contour_color = Color::rgba(0.9, 0.7, 0.3, 0.8);
+
+    for y in y_range.clone() {
+        for x in x_range.clone() {
+            let center = Vec2::new(x as f32 * 10.0, y as f32 * 10.0);
+            let terrain = tile_map.terrain(x, y).unwrap_or(TerrainType::Flat);
+            let elevation = tile_map.elevation(x, y).unwrap_or(0.0);
+            let mut color = tile_color(terrain, elevation, relief, display.shading);
+            if let Some(overlay) = tint(display.overlay, tile_map, coverage, x, y) {
+                color = blend(color, overlay);
+            }
+            push_quad(center - Vec2::splat(5.0), center + Vec2::splat(5.0), 0.0, color);
+
+            if display.contours {
+                let here = band(tile_map, x, y);
+                let half = CONTOUR_WIDTH / 2.0;
+                if x + 1 < tile_map.width && band(tile_map, x + 1, y) != here {
+                    push_quad(
+                        center + Vec2::new(5.0 - half, -5.0),
+                        center + Vec2::new(5.0 + half, 5.0),
+                        0.1,
+                        contour_color,
+                    );
+                }
+                if y + 1 < tile_map.height && band(tile_map, x, y + 1) != here {
+                    push_quad(
+                        center + Vec2::new(-5.0, 5.0 - half),
+                        center + Vec2::new(5.0, 5.0 + half),
+                        0.1,
+                        contour_color,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// World-space rectangle the camera currently shows
+pub fn camera_view(transform: &Transform, projection: &OrthographicProjection) -> Rect {
+    let center = transform.translation.truncate();
+    let scale = transform.scale.truncate();
+    Rect::from_corners(center + projection.area.min * scale, center + projection.area.max * scale)
+}
+
+/// Chunks overlapping `view`, plus `VIEW_MARGIN` around them
+pub fn visible_chunks(tile_map: &TileMap, view: Rect) -> HashSet<(u32, u32)> {
+    let (count_x, count_y) = tile_map.chunk_count();
+    // Tiles are centred on multiples of 10, so tile n spans 10n - 5 to 10n + 5
+    let to_chunk = |world: f32| (((world + 5.0) / 10.0).floor() as i64).div_euclid(CHUNK_SIZE as i64);
+    let (min_x, max_x) = (to_chunk(view.min.x), to_chunk(view.max.x));
+    let (min_y, max_y) = (to_chunk(view.min.y), to_chunk(view.max.y));
+    let margin = VIEW_MARGIN as i64;
+
+    let mut chunks = HashSet::new();
+    for cy in (min_y - margin).max(0)..=(max_y + margin).min(count_y as i64 - 1) {
+        for cx in (min_x - margin).max(0)..=(max_x + margin).min(count_x as i64 - 1) {
+            chunks.insert((cx as u32, cy as u32));
+        }
+    }
+    chunks
+}
+
+/// Keep a mesh for each chunk near the camera and drop the rest. Chunks whose
+/// tiles changed are re-meshed, and every visible chunk is when the display
+/// options or the overlay's coverage change.
+pub fn update_terrain_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut material: Local<Option<Handle<ColorMaterial>>>,
+    mut tile_map: ResMut<TileMap>,
+    display: Res<MapDisplay>,
+    coverage: Res<Coverage>,
+    camera: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+    chunks: Query<(Entity, &TerrainChunk, &Mesh2dHandle)>,
+) {
+    let mut dirty = tile_map.take_dirty_chunks();
+    let Ok((transform, projection)) = camera.get_single() else {
+        return;
+    };
+    let visible = visible_chunks(&tile_map, camera_view(transform, projection));
+
+    // Elevation changes shift contour bands and shading next door too
+    let neighbours: Vec<(u32, u32)> = dirty
+        .iter()
+        .flat_map(|&(cx, cy)| {
+            [(cx + 1, cy), (cx, cy + 1), (cx.wrapping_sub(1), cy), (cx, cy.wrapping_sub(1))]
+        })
+        .collect();
+    dirty.extend(neighbours);
+
+    let mut meshed = HashSet::new();
+    let mut stale = Vec::new();
+    for (entity, chunk, handle) in chunks.iter() {
+        let key = (chunk.cx, chunk.cy);
+        if !visible.contains(&key) {
+            meshes.remove(&handle.0);
+            commands.entity(entity).despawn();
+            continue;
+        }
+        meshed.insert(key);
+        if display.is_changed() || coverage.is_changed() || dirty.contains(&key) {
+            stale.push((key, handle.0.clone()));
+        }
+    }
+    let missing: Vec<(u32, u32)> = visible.difference(&meshed).copied().collect();
+    if stale.is_empty() && missing.is_empty() {
+        return;
+    }
+
+    let relief = relief(&tile_map);
+    for ((cx, cy), handle) in stale {
+        meshes.insert(handle, build_chunk_mesh(&tile_map, cx, cy, relief, &display, &coverage));
+    }
+    let material = material
+        .get_or_insert_with(|| materials.add(ColorMaterial::default()))
+        .clone();
+    for (cx, cy) in missing {
+        commands.spawn((
+            TerrainChunk { cx, cy },
+            MaterialMesh2dBundle {
+                mesh: meshes.add(build_chunk_mesh(&tile_map, cx, cy, relief, &display, &coverage)).into(),
+                material: material.clone(),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Grid lines along tile edges, limited to the part of the map in view
+pub fn draw_grid(
+    mut gizmos: Gizmos,
+    tile_map: Res<TileMap>,
+    display: Res<MapDisplay>,
+    camera: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+) {
+    if !display.grid {
+        return;
+    }
+    let Ok((transform, projection)) = camera.get_single() else {
+        return;
+    };
+    let view = camera_view(transform, projection);
+    // Grid lines run along tile edges, half a tile either side of the centres
+    let edge = |n: u32| n as f32 * 10.0 - 5.0;
+    let first = |world: f32, size: u32| (((world + 5.0) / 10.0).floor().max(0.0) as u32).min(size);
+    let last = |world: f32, size: u32| (((world + 5.0) / 10.0).ceil().max(0.0) as u32).min(size);
+    let (x0, x1) = (first(view.min.x, tile_map.width), last(view.max.x, tile_map.width));
+    let (y0, y1) = (first(view.min.y, tile_map.height), last(view.max.y, tile_map.height));
+
+    for x in x0..=x1 {
+        gizmos.line_2d(
+            Vec2::new(edge(x), edge(y0)),
+            Vec2::new(edge(x), edge(y1)),
+            Color::rgba(1.0, 1.0, 1.0, 0.2),
+        );
+    }
+    for y in y0..=y1 {
+        gizmos.line_2d(
+            Vec2::new(edge(x0), edge(y)),
+            Vec2::new(edge(x1), edge(y)),
+            Color::rgba(1.0, 1.0, 1.0, 0.2),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::MapTile;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    fn tile_map(width: u32, height: u32, elevation: impl Fn(u32, u32) -> f32) -> TileMap {
+        let mut map = TileMap::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                map.set(&MapTile { x, y, terrain: TerrainType::Flat, elevation: elevation(x, y), slope: 0.0, helium3: 0.0 });
+            }
+        }
+        map
+    }
+
+    fn vertex_count(mesh: &Mesh) -> usize {
+        match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions.len(),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_chunk_mesh_covers_its_tiles() {
+        let display = MapDisplay { shading: true, contours: false, grid: true, ..default() };
+        let coverage = Coverage::default();
+        let map = tile_map(40, 40, |_, _| 0.0);
+        let relief = relief(&map);
+
+        // A full chunk and a partial one on the map edge
+        assert_eq!(vertex_count(&build_chunk_mesh(&map, 0, 0, relief, &display, &coverage)), (CHUNK_SIZE * CHUNK_SIZE * 4) as usize);
+        assert_eq!(vertex_count(&build_chunk_mesh(&map, 1, 1, relief, &display, &coverage)), 8 * 8 * 4);
+        assert!(build_chunk_mesh(&map, 0, 0, relief, &display, &coverage).attribute(Mesh::ATTRIBUTE_COLOR).is_some());
+    }
+
+    #[test]
+    fn test_chunk_mesh_contours() {
+        // One step of a full contour interval between x = 0 and x = 1
+        let map = tile_map(2, 2, |x, _| x as f32 * CONTOUR_INTERVAL);
+        let plain = MapDisplay { shading: false, contours: false, grid: false, ..default() };
+        let contours = MapDisplay { shading: false, contours: true, grid: false, ..default() };
+
+        assert_eq!(vertex_count(&build_chunk_mesh(&map, 0, 0, relief(&map), &plain, &Coverage::default())), 4 * 4);
+        assert_eq!(vertex_count(&build_chunk_mesh(&map, 0, 0, relief(&map), &contours, &Coverage::default())), 4 * 4 + 2 * 4);
+    }
+
+    #[test]
+    fn test_visible_chunks() {
+        let map = TileMap::new(1024, 1024);
+        // Exactly chunk (1, 1), which spans tiles 32..64
+        let view = Rect::new(315.0, 315.0, 634.0, 634.0);
+        let chunks = visible_chunks(&map, view);
+        assert_eq!(chunks.len(), 9);
+        assert!(chunks.contains(&(0, 0)) && chunks.contains(&(2, 2)));
+
+        // Clipped at the map edge, and nothing at all far off the map
+        assert_eq!(visible_chunks(&map, Rect::new(-500.0, -500.0, 0.0, 0.0)).len(), 4);
+        assert!(visible_chunks(&map, Rect::new(-2000.0, -2000.0, -1000.0, -1000.0)).is_empty());
+    }
+
+    #[test]
+    fn test_chunks_stream_with_the_camera() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<MapDisplay>()
+            .init_resource::<Coverage>()
+            .insert_resource(TileMap::new(1024, 1024))
+            .add_systems(Update, update_terrain_chunks);
+        let projection = OrthographicProjection {
+            area: Rect::new(-400.0, -300.0, 400.0, 300.0),
+            ..default()
+        };
+        let camera = app.world.spawn((Camera2d::default(), Transform::from_xyz(320.0, 320.0, 0.0), projection)).id();
+
+        let chunk_keys = |app: &mut App| {
+            let mut keys: Vec<_> = app.world.query::<&TerrainChunk>().iter(&app.world).map(|c| (c.cx, c.cy)).collect();
+            keys.sort();
+            keys
+        };
+
+        app.update();
+        let near_origin = chunk_keys(&mut app);
+        assert!(!near_origin.is_empty());
+        assert!(near_origin.len() < 32, "only chunks near the camera are meshed, got {}", near_origin.len());
+        assert_eq!(app.world.resource::<Assets<Mesh>>().len(), near_origin.len());
+
+        // Pan to the far corner: the old chunks go, new ones come in
+        app.world.get_mut::<Transform>(camera).unwrap().translation = Vec3::new(10_000.0, 10_000.0, 0.0);
+        app.update();
+        let far_corner = chunk_keys(&mut app);
+        assert!(far_corner.iter().all(|key| !near_origin.contains(key)));
+        assert_eq!(app.world.resource::<Assets<Mesh>>().len(), far_corner.len());
+    }
+}
@@ -1,13 +1,42 @@
+use bevy::ecs::system::SystemParam;
+use bevy::app::AppExit;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use bevy_egui::{egui, EguiContexts};
+use crate::buildings::{self, Building, BuildingKind, BuildingStatus, Demolish, PowerCable, Product};
+use crate::construction::{self, ConstructionFinished, ConstructionSite};
+use crate::events::{EventLog, RandomEvent};
+use crate::finance::{Account, Treasury, DAYS_PER_MONTH};
+use crate::funding::{self, Funding};
 use crate::game_state::{Tool, SelectedTool, GameState};
+use crate::history::{Action, History, HistoryRequest, Recorder, Structure};
+use crate::leveling::{self, LevelingJob};
+use crate::logistics::{self, Shipping};
+use crate::market::{self, Commodity, Market, Stockpile};
+use crate::maintenance::{MaintenanceBudget, SolarFlare};
+use crate::map::TileMap;
+use crate::mb::{MbImage, EGA_PLANES};
+use crate::mission::{CompletedObjectives, MapDisplay, Mission, MissionFailed, TerrainType};
+use crate::news::{NewsKind, Ticker};
+use crate::overlay::Overlay;
+use crate::simulation::{oxygen_days_left, GameClock, LifeSupport, Research, Workforce};
+use crate::simulation::{CRATER_FILLING_POINTS, LANDER_HOUSING, OXYGEN_PER_CREW};
+use crate::save::{PendingLoad, SaveGame, SaveRequest, SAVE_FILE};
+use crate::tilemap::{camera_view, relief, tile_color};
 
 const TOOLBAR_WIDTH: f32 = 60.0;
 
+/// Square map sizes offered in the New Game dialog
+pub const MAP_SIZES: [u32; 4] = [64, 256, 1024, 2048];
+
 #[derive(Resource)]
 pub struct NewGameDialog {
     pub open: bool,
     pub selected_mission: usize,
+    pub seed_text: String,
+    pub map_size: u32,
+    /// Start the selected mission once the running one has been torn down
+    pub restart: bool,
 }
 
 impl Default for NewGameDialog {
@@ -15,16 +44,119 @@ impl Default for NewGameDialog {
         Self {
             open: false,
             selected_mission: 1,
+            seed_text: random_seed_text(),
+            map_size: MAP_SIZES[0],
+            restart: false,
         }
     }
 }
 
+/// Whether the Financial Report window is open, the month it shows and the
+/// size of loan being asked for
+#[derive(Resource, Default)]
+pub struct FinancialReport {
+    pub open: bool,
+    pub month: u32,
+    pub loan_amount: f32,
+}
+
+/// Whether the Trade Center window is open, the commodity whose price
+/// history it graphs and how much each trade moves
+#[derive(Resource)]
+pub struct TradeCenter {
+    pub open: bool,
+    pub selected: Commodity,
+    pub quantity: f32,
+}
+
+impl Default for TradeCenter {
+    fn default() -> Self {
+        Self { open: false, selected: Commodity::Helium3, quantity: 10.0 }
+    }
+}
+
+/// Whether the Event Log window is open
+#[derive(Resource, Default)]
+pub struct EventLogWindow {
+    pub open: bool,
+}
+
+/// Whether the Mission Control dashboard is open
+#[derive(Resource, Default)]
+pub struct MissionControl {
+    pub open: bool,
+}
+
+/// Whether the Map View minimap is open
+#[derive(Resource, Default)]
+pub struct MapViewWindow {
+    pub open: bool,
+}
+
+/// The Load window, the save file it reads and what was last read from it
+#[derive(Resource)]
+pub struct LoadDialog {
+    pub open: bool,
+    pub path: String,
+    preview: Option<(String, Result<SaveGame, String>)>,
+}
+
+impl Default for LoadDialog {
+    fn default() -> Self {
+        Self { open: false, path: SAVE_FILE.to_string(), preview: None }
+    }
+}
+
+/// Whether the Settings window is open
+#[derive(Resource, Default)]
+pub struct SettingsWindow {
+    pub open: bool,
+}
+
+/// Whether the Credits window is open
+#[derive(Resource, Default)]
+pub struct CreditsWindow {
+    pub open: bool,
+}
+
+/// The windows that menus open
+#[derive(SystemParam)]
+pub struct Windows<'w> {
+    pub load: ResMut<'w, LoadDialog>,
+    pub settings: ResMut<'w, SettingsWindow>,
+    pub credits: ResMut<'w, CreditsWindow>,
+    pub map_view: ResMut<'w, MapViewWindow>,
+    pub financial_report: ResMut<'w, FinancialReport>,
+    pub trade_center: ResMut<'w, TradeCenter>,
+    pub event_log: ResMut<'w, EventLogWindow>,
+    pub mission_control: ResMut<'w, MissionControl>,
+}
+
+/// Building shown in the inspection panel, if any
+#[derive(Resource, Default)]
+pub struct InspectedBuilding {
+    pub entity: Option<Entity>,
+}
+
+fn random_seed_text() -> String {
+    rand::random::<u32>().to_string()
+}
+
 pub fn top_menu_bar(
     mut contexts: EguiContexts,
     mut new_game_dialog: ResMut<NewGameDialog>,
-    mut next_state: ResMut<NextState<GameState>>,
     mut camera: Query<&mut Transform, With<Camera2d>>,
-    mission: Option<Res<crate::mission::Mission>>,
+    mission: Option<Res<Mission>>,
+    mut map_display: ResMut<MapDisplay>,
+    treasury: Option<Res<Treasury>>,
+    clock: Option<Res<GameClock>>,
+    history: Option<Res<History>>,
+    mut history_requests: EventWriter<HistoryRequest>,
+    maintenance: Option<ResMut<MaintenanceBudget>>,
+    flare: Option<Res<SolarFlare>>,
+    mut windows: Windows,
+    mut exit: EventWriter<AppExit>,
+    mut saves: EventWriter<SaveRequest>,
 ) {
     egui::TopBottomPanel::top("top_panel").show(contexts.ctx_mut(), |ui| {
         egui::menu::bar(ui, |ui| {
@@ -32,22 +164,38 @@ pub fn top_menu_bar(
                 if ui.button("New Game").clicked() {
                     new_game_dialog.open = true;
                 }
-                if ui.button("Save").clicked() {
-                    // Handle save
+                if ui.add_enabled(mission.is_some(), egui::Button::new("Save")).clicked() {
+                    saves.send(SaveRequest { path: SAVE_FILE.to_string() });
+                    ui.close_menu();
                 }
                 if ui.button("Load").clicked() {
-                    // Start mission 1 directly
-                    new_game_dialog.selected_mission = 1;
-                    next_state.set(GameState::Playing);
+                    windows.load.open = true;
+                    ui.close_menu();
+                }
+                if ui.button("Settings").clicked() {
+                    windows.settings.open = true;
+                    ui.close_menu();
                 }
+                ui.separator();
                 if ui.button("Exit").clicked() {
-                    // Handle exit
+                    exit.send(AppExit);
+                }
+            });
+
+            ui.menu_button("Edit", |ui| {
+                let can_undo = history.as_ref().is_some_and(|history| history.can_undo());
+                if ui.add_enabled(can_undo, egui::Button::new("Undo").shortcut_text("Ctrl+Z")).clicked() {
+                    history_requests.send(HistoryRequest::Undo);
+                }
+                let can_redo = history.as_ref().is_some_and(|history| history.can_redo());
+                if ui.add_enabled(can_redo, egui::Button::new("Redo").shortcut_text("Ctrl+Y")).clicked() {
+                    history_requests.send(HistoryRequest::Redo);
                 }
             });
 
             ui.menu_button("View", |ui| {
                 if ui.button("Center Map").clicked() {
-                    if let Some(mission) = mission {
+                    if let Some(mission) = &mission {
                         if let Ok(mut transform) = camera.get_single_mut() {
                             let (width, height) = mission.map_size;
                             transform.translation.x = (width as f32 * 10.0) / 2.0;
@@ -56,26 +204,320 @@ pub fn top_menu_bar(
                         }
                     }
                 }
-                if ui.button("Map View").clicked() {}
-                if ui.button("Financial Report").clicked() {}
+                display_options(ui, &mut map_display);
+                ui.separator();
+                if ui.add_enabled(mission.is_some(), egui::Button::new("Map View")).clicked() {
+                    windows.map_view.open = true;
+                    ui.close_menu();
+                }
+                if ui.add_enabled(treasury.is_some(), egui::Button::new("Financial Report")).clicked() {
+                    windows.financial_report.open = true;
+                    ui.close_menu();
+                }
+                if ui.add_enabled(treasury.is_some(), egui::Button::new("Event Log")).clicked() {
+                    windows.event_log.open = true;
+                    ui.close_menu();
+                }
             });
 
             ui.menu_button("Markets", |ui| {
-                if ui.button("Trade Center").clicked() {}
-                if ui.button("Research").clicked() {}
+                if ui.add_enabled(treasury.is_some(), egui::Button::new("Trade Center")).clicked() {
+                    windows.trade_center.open = true;
+                    ui.close_menu();
+                }
+                ui.add_enabled(false, egui::Button::new("Research"));
             });
 
             ui.menu_button("Operations", |ui| {
-                if ui.button("Mission Control").clicked() {}
-                if ui.button("Staff Management").clicked() {}
+                if ui.add_enabled(mission.is_some(), egui::Button::new("Mission Control")).clicked() {
+                    windows.mission_control.open = true;
+                    ui.close_menu();
+                }
+                ui.add_enabled(false, egui::Button::new("Staff Management"));
+                if let Some(mut maintenance) = maintenance {
+                    ui.separator();
+                    let slider = egui::Slider::new(&mut maintenance.share, 0.0..=1.0).text("Maintenance budget");
+                    ui.add(slider).on_hover_text("Slows wear and breakdowns, at a daily cost and some crew time");
+                }
+            });
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if let Some(treasury) = treasury {
+                    let funds = format!("Funds: ${:.0}", treasury.balance());
+                    if treasury.balance() < 0.0 {
+                        ui.colored_label(egui::Color32::LIGHT_RED, funds);
+                    } else {
+                        ui.label(funds);
+                    }
+                }
+                if let Some(clock) = clock {
+                    ui.label(format!("Day {}", clock.day + 1));
+                }
+                if let Some(flare) = flare {
+                    ui.colored_label(egui::Color32::YELLOW, format!("☀ Solar flare ({} days)", flare.days_left));
+                }
+            });
+        });
+    });
+}
+
+/// Start the mission set up in the New Game dialog. A mission already under
+/// way is torn down first by passing through the title screen.
+fn start_game(state: &State<GameState>, new_game_dialog: &mut NewGameDialog, next_state: &mut NextState<GameState>) {
+    if *state.get() == GameState::MainMenu {
+        next_state.set(GameState::Playing);
+    } else {
+        new_game_dialog.restart = true;
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+/// Start the mission a save file describes and restore its progress, keeping
+/// its settings in the New Game dialog so they show up there next time
+fn load_game(
+    save: &SaveGame,
+    commands: &mut Commands,
+    state: &State<GameState>,
+    new_game_dialog: &mut NewGameDialog,
+    next_state: &mut NextState<GameState>,
+) {
+    new_game_dialog.selected_mission = save.mission_id;
+    new_game_dialog.seed_text = save.seed.to_string();
+    new_game_dialog.map_size = save.map_size.0;
+    match &save.progress {
+        Some(progress) => commands.insert_resource(PendingLoad(progress.clone())),
+        None => commands.remove_resource::<PendingLoad>(),
+    }
+    start_game(state, new_game_dialog, next_state);
+}
+
+/// Pixel size of each logo pixel on the title screen
+const LOGO_SCALE: f32 = 2.0;
+
+/// The title screen shown at startup, with the original game's logo when its
+/// data files are present
+pub fn main_menu(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut new_game_dialog: ResMut<NewGameDialog>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut windows: Windows,
+    mut exit: EventWriter<AppExit>,
+    mut logo: Local<Option<Option<egui::TextureHandle>>>,
+    mut saved: Local<Option<SaveGame>>,
+) {
+    // Read the save each time the menu comes up, as the game may have been
+    // saved since. Only a save with progress in it is worth continuing.
+    if state.is_changed() {
+        *saved = SaveGame::read(SAVE_FILE).ok().filter(|save| save.progress.is_some());
+    }
+    if new_game_dialog.restart {
+        new_game_dialog.restart = false;
+        next_state.set(GameState::Playing);
+        return;
+    }
+    let ctx = contexts.ctx_mut().clone();
+    let logo = logo.get_or_insert_with(|| match MbImage::open(MbImage::logo_path(), EGA_PLANES) {
+        Ok(image) => {
+            let pixels = egui::ColorImage::from_rgba_unmultiplied([image.width, image.height], &image.to_rgba());
+            Some(ctx.load_texture("logo", pixels, egui::TextureOptions::NEAREST))
+        }
+        Err(err) => {
+            warn!("Could not load the title logo: {}", err);
+            None
+        }
+    });
+
+    egui::CentralPanel::default().show(&ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() * 0.1);
+            match logo {
+                Some(texture) => {
+                    ui.image((texture.id(), texture.size_vec2() * LOGO_SCALE));
+                }
+                None => {
+                    ui.heading(egui::RichText::new("MOONBASE").size(64.0).strong());
+                }
+            }
+            ui.add_space(24.0);
+
+            let button = |ui: &mut egui::Ui, enabled: bool, text: &str| {
+                ui.add_enabled(enabled, egui::Button::new(text).min_size(egui::vec2(200.0, 32.0)))
+            };
+            if button(ui, true, "New Game").clicked() {
+                new_game_dialog.open = true;
+            }
+            let progress = saved.as_ref().and_then(|save| save.progress.as_ref());
+            let continue_text = match progress {
+                Some(progress) => format!("Day {}, ${:.0} in funds", progress.clock.day, progress.treasury.balance()),
+                None => "No game in progress has been saved".to_string(),
+            };
+            let continued = button(ui, saved.is_some(), "Continue");
+            if continued.on_hover_text(&continue_text).on_disabled_hover_text(&continue_text).clicked() {
+                if let Some(save) = saved.as_ref() {
+                    load_game(save, &mut commands, &state, &mut new_game_dialog, &mut next_state);
+                }
+            }
+            if button(ui, true, "Load").clicked() {
+                windows.load.open = true;
+            }
+            if button(ui, true, "Settings").clicked() {
+                windows.settings.open = true;
+            }
+            if button(ui, true, "Credits").clicked() {
+                windows.credits.open = true;
+            }
+            if button(ui, true, "Quit").clicked() {
+                exit.send(AppExit);
+            }
+        });
+    });
+}
+
+/// Shown while the game is paused. The simulation doesn't run in this state,
+/// so the clock stands still until the player resumes.
+pub fn pause_menu(
+    mut contexts: EguiContexts,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut windows: Windows,
+    mission: Option<Res<Mission>>,
+    mut saves: EventWriter<SaveRequest>,
+) {
+    egui::Window::new("Paused")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(contexts.ctx_mut(), |ui| {
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Resume").clicked() {
+                    next_state.set(GameState::Playing);
+                }
+                let save = ui
+                    .add_enabled(mission.is_some(), egui::Button::new("Save"))
+                    .on_hover_text(format!("Write the whole game to {}", SAVE_FILE));
+                if save.clicked() {
+                    saves.send(SaveRequest { path: SAVE_FILE.to_string() });
+                }
+                if ui.button("Load").on_hover_text("Anything not saved in this game is lost").clicked() {
+                    windows.load.open = true;
+                }
+                if ui.button("Settings").clicked() {
+                    windows.settings.open = true;
+                }
+                ui.separator();
+                if ui.button("Quit to Menu").clicked() {
+                    next_state.set(GameState::MainMenu);
+                }
             });
         });
+}
+
+/// Pick a save file and show what it holds before loading it. The file is
+/// read when the window opens and again only when the path is edited.
+pub fn load_dialog(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<LoadDialog>,
+    mut new_game_dialog: ResMut<NewGameDialog>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !dialog.open {
+        return;
+    }
+    if dialog.preview.as_ref().map_or(true, |(path, _)| *path != dialog.path) {
+        let save = SaveGame::read(&dialog.path);
+        dialog.preview = Some((dialog.path.clone(), save));
+    }
+
+    let mut open = true;
+    let mut load = false;
+    let dialog = &mut *dialog;
+    egui::Window::new("Load Game").open(&mut open).collapsible(false).resizable(false).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Save file:");
+            ui.text_edit_singleline(&mut dialog.path);
+        });
+        let Some((_, save)) = &dialog.preview else {
+            return;
+        };
+        match save {
+            Ok(save) => {
+                ui.label(format!(
+                    "Mission {}, seed {}, {}x{} map",
+                    save.mission_id, save.seed, save.map_size.0, save.map_size.1
+                ));
+                match &save.progress {
+                    Some(progress) => ui.label(format!(
+                        "Day {}, funds ${:.0}, {} buildings",
+                        progress.clock.day + 1,
+                        progress.treasury.balance(),
+                        progress.buildings.len()
+                    )),
+                    None => ui.label("No progress saved: the mission starts over"),
+                };
+            }
+            Err(err) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, err);
+            }
+        }
+        ui.add_space(8.0);
+        if *state.get() != GameState::MainMenu {
+            ui.colored_label(egui::Color32::YELLOW, "The game under way is replaced: save it first to keep it");
+        }
+        load = ui.add_enabled(save.is_ok(), egui::Button::new("Load")).clicked();
+    });
+    if load {
+        if let Some((_, Ok(save))) = &dialog.preview {
+            load_game(save, &mut commands, &state, &mut new_game_dialog, &mut next_state);
+        }
+        open = false;
+    }
+    dialog.open = open;
+    if !open {
+        dialog.preview = None;
+    }
+}
+
+/// Display options, shared by the title screen and the game
+pub fn settings(mut contexts: EguiContexts, mut window: ResMut<SettingsWindow>, mut display: ResMut<MapDisplay>) {
+    egui::Window::new("Settings").open(&mut window.open).resizable(false).show(contexts.ctx_mut(), |ui| {
+        ui.heading("Map");
+        display_options(ui, &mut display);
+    });
+}
+
+/// Checkboxes for the map display. They edit a copy, as touching the
+/// display re-meshes the whole map, and only write back a change.
+fn display_options(ui: &mut egui::Ui, display: &mut ResMut<MapDisplay>) {
+    let (mut shading, mut contours, mut grid) = (display.shading, display.contours, display.grid);
+    ui.checkbox(&mut shading, "Elevation Shading");
+    ui.checkbox(&mut contours, "Contour Lines");
+    ui.checkbox(&mut grid, "Grid Overlay");
+    if (shading, contours, grid) != (display.shading, display.contours, display.grid) {
+        display.shading = shading;
+        display.contours = contours;
+        display.grid = grid;
+    }
+}
+
+pub fn credits(mut contexts: EguiContexts, mut window: ResMut<CreditsWindow>) {
+    egui::Window::new("Credits").open(&mut window.open).resizable(false).show(contexts.ctx_mut(), |ui| {
+        ui.vertical_centered(|ui| {
+            ui.heading("Moonbase Online");
+            ui.label("A Rust remake of the 1990 DOS simulation Moonbase");
+            ui.add_space(8.0);
+            ui.label("Original game and logo art by Wesson International");
+            ui.label("Built with Bevy and egui");
+        });
     });
 }
 
 pub fn new_game_dialog(
     mut contexts: EguiContexts,
     mut new_game_dialog: ResMut<NewGameDialog>,
+    state: Res<State<GameState>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     if new_game_dialog.open {
@@ -86,7 +528,7 @@ pub fn new_game_dialog(
                 ui.heading("Select Mission");
                 ui.add_space(8.0);
                 
-                for mission in 1..=6 {
+                for mission in 1..=Mission::COUNT {
                     let text = match mission {
                         1 => "Mission 1: First Steps - Basic Base Setup",
                         2 => "Mission 2: Power Grid - Energy Management",
@@ -102,11 +544,26 @@ pub fn new_game_dialog(
                     }
                 }
                 
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.label("Map seed:");
+                    ui.text_edit_singleline(&mut new_game_dialog.seed_text);
+                    if ui.button("🎲").on_hover_text("Random seed").clicked() {
+                        new_game_dialog.seed_text = random_seed_text();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Map size:");
+                    for size in MAP_SIZES {
+                        ui.radio_value(&mut new_game_dialog.map_size, size, format!("{size}x{size}"));
+                    }
+                });
+
                 ui.add_space(16.0);
                 ui.horizontal(|ui| {
                     if ui.button("Start Mission").clicked() {
                         new_game_dialog.open = false;
-                        next_state.set(GameState::Playing);
+                        start_game(&state, &mut new_game_dialog, &mut next_state);
                     }
                     if ui.button("Cancel").clicked() {
                         new_game_dialog.open = false;
@@ -119,11 +576,15 @@ pub fn new_game_dialog(
 pub fn side_toolbar(
     mut contexts: EguiContexts,
     mut commands: Commands,
+    research: Option<Res<Research>>,
 ) {
     egui::SidePanel::left("toolbar")
         .exact_width(TOOLBAR_WIDTH)
         .show(contexts.ctx_mut(), |ui| {
             ui.vertical_centered(|ui| {
+                if ui.button("🖱").on_hover_text("Select and inspect").clicked() {
+                    commands.insert_resource(SelectedTool { tool: Tool::None });
+                }
                 if ui.button("🏠").clicked() {
                     commands.insert_resource(SelectedTool { tool: Tool::LivingModule });
                 }
@@ -142,23 +603,1023 @@ pub fn side_toolbar(
                 if ui.button("⛏️").clicked() {
                     commands.insert_resource(SelectedTool { tool: Tool::HeliumMine });
                 }
+                if ui.button("☀").on_hover_text("Solar array").clicked() {
+                    commands.insert_resource(SelectedTool { tool: Tool::SolarArray });
+                }
+                if ui.button("🚀").on_hover_text("Landing pad for supply shuttles").clicked() {
+                    commands.insert_resource(SelectedTool { tool: Tool::LandingPad });
+                }
+                let crater_filling = research.as_ref().is_some_and(|r| r.crater_filling);
+                let level_hint = if crater_filling {
+                    "Level rough ground or fill craters".to_string()
+                } else {
+                    format!(
+                        "Level rough ground (crater filling needs {:.0}/{:.0} research)",
+                        research.as_ref().map_or(0.0, |r| r.points),
+                        CRATER_FILLING_POINTS,
+                    )
+                };
+                if ui.button("🚜").on_hover_text(level_hint).clicked() {
+                    commands.insert_resource(SelectedTool { tool: Tool::Level });
+                }
+                if ui.button("💣").on_hover_text("Bulldoze buildings and cables for a partial refund").clicked() {
+                    commands.insert_resource(SelectedTool { tool: Tool::Bulldoze });
+                }
             });
         });
 }
 
+/// The primary window's cursor, projected into the world
+#[derive(SystemParam)]
+pub struct WorldCursor<'w, 's> {
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl WorldCursor<'_, '_> {
+    pub fn position(&self) -> Option<Vec2> {
+        let (Ok(window), Ok((camera, camera_transform))) = (self.windows.get_single(), self.camera.get_single()) else {
+            return None;
+        };
+        window
+            .cursor_position()
+            .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    }
+}
+
+/// The treasury, and whether the mission has failed and spending is over
+#[derive(SystemParam)]
+pub struct Funds<'w> {
+    treasury: Option<ResMut<'w, Treasury>>,
+    failed: Option<Res<'w, MissionFailed>>,
+}
+
 pub fn handle_tool_selection(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
     tool: Option<Res<SelectedTool>>,
     buttons: Res<Input<MouseButton>>,
-    windows: Query<&Window>,
+    cursor: WorldCursor,
+    tile_map: Option<Res<TileMap>>,
+    buildings: Query<(Entity, &Building)>,
+    sites: Query<(Entity, &ConstructionSite)>,
+    cables: Query<(Entity, &PowerCable)>,
+    jobs: Query<&LevelingJob>,
+    funds: Funds,
+    research: Option<Res<Research>>,
+    mut history: Recorder,
+    mut inspected: ResMut<InspectedBuilding>,
+    mut demolish: EventWriter<Demolish>,
+    mut cable_start: Local<Option<(u32, u32)>>,
+) {
+    let (Some(tile_map), Some(mut treasury)) = (tile_map, funds.treasury) else {
+        return;
+    };
+    let tool = tool.map_or(Tool::None, |selected| selected.tool);
+    if !buttons.just_pressed(MouseButton::Left) || contexts.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let Some(tile_pos) = cursor.position().and_then(|world_pos| tile_map.tile_at(world_pos)) else {
+        return;
+    };
+
+    let building_at = |pos: (u32, u32)| buildings.iter().find(|(_, b)| (b.x, b.y) == pos).map(|(entity, _)| entity);
+    let site_at = |pos: (u32, u32)| {
+        let site = sites.iter().find(|(_, site)| (site.building.x, site.building.y) == pos);
+        site.map(|(entity, _)| entity)
+    };
+    let occupied = |pos: (u32, u32)| building_at(pos).is_some() || site_at(pos).is_some();
+    let cabled = |pos: (u32, u32)| cables.iter().any(|(_, c)| (c.x, c.y) == pos);
+    let leveling = |pos: (u32, u32)| jobs.iter().any(|job| (job.x, job.y) == pos);
+
+    if tool == Tool::None {
+        inspected.entity = building_at(tile_pos);
+    } else if funds.failed.is_some() {
+        info!("The mission has failed: no more orders can be given");
+    } else if let Some(kind) = BuildingKind::from_tool(tool) {
+        // Leveling would shift the ground under a site after its cost was set
+        if occupied(tile_pos) || cabled(tile_pos) || leveling(tile_pos) {
+            info!("Tile {:?} is already in use", tile_pos);
+            return;
+        }
+        let Some((terrain, slope)) = tile_map.surface(tile_pos.0, tile_pos.1) else {
+            return;
+        };
+        let Some(cost) = buildings::construction_cost(kind.base_cost(), terrain, slope) else {
+            info!("Cannot build {:?} on {:?} with slope {:.2}", kind, terrain, slope);
+            return;
+        };
+        if !treasury.can_afford(cost) {
+            info!("Not enough funds for {:?}: need ${:.0}", kind, cost);
+            return;
+        }
+        treasury.spend(Account::Construction, cost);
+        let site = ConstructionSite::new(Building::new(kind, tile_pos.0, tile_pos.1, cost), terrain, slope);
+        let entity = construction::spawn_site(&mut commands, site.clone());
+        history.record(Action::Build { placed: vec![(entity, Structure::Site(site))], cost });
+    } else if tool == Tool::Bulldoze {
+        let target = building_at(tile_pos)
+            .or_else(|| site_at(tile_pos))
+            .or_else(|| cables.iter().find(|(_, c)| (c.x, c.y) == tile_pos).map(|(entity, _)| entity));
+        if let Some(entity) = target {
+            demolish.send(Demolish { entity });
+        }
+    } else if tool == Tool::Level {
+        if occupied(tile_pos) || leveling(tile_pos) {
+            info!("Tile {:?} is already in use", tile_pos);
+            return;
+        }
+        let Some((terrain, slope)) = tile_map.surface(tile_pos.0, tile_pos.1) else {
+            return;
+        };
+        let crater_filling = research.is_some_and(|r| r.crater_filling);
+        let Some((cost, work_days)) = leveling::leveling_job(terrain, slope, crater_filling) else {
+            info!("Cannot level {:?} here", terrain);
+            return;
+        };
+        if !treasury.can_afford(cost) {
+            info!("Not enough funds for leveling: need ${:.0}", cost);
+            return;
+        }
+        let Some(tile) = tile_map.tile(tile_pos.0, tile_pos.1) else {
+            return;
+        };
+        treasury.spend(Account::Construction, cost);
+        let job = LevelingJob::new(tile_pos.0, tile_pos.1, work_days);
+        let entity = commands.spawn(job.clone()).id();
+        history.record(Action::Level { entity, job, tile, cost });
+    } else if tool == Tool::PowerCable {
+        // First click picks the start of the run, second click lays it
+        let Some(start) = cable_start.take() else {
+            *cable_start = Some(tile_pos);
+            return;
+        };
+        let Some(route) = buildings::route_cable(start, tile_pos, &tile_map) else {
+            info!("No cable route from {:?} to {:?}", start, tile_pos);
+            return;
+        };
+        // Buildings conduct on their own, so the run only needs the tiles between them
+        let new_tiles: Vec<_> = route
+            .into_iter()
+            .filter(|&pos| !cabled(pos) && !occupied(pos))
+            .map(|(x, y)| {
+                let step = tile_map.surface(x, y).and_then(|(terrain, slope)| crate::terrain::travel_cost(terrain, slope));
+                (x, y, step.unwrap_or(1.0) * buildings::CABLE_COST_PER_TILE)
+            })
+            .collect();
+        let cost: f32 = new_tiles.iter().map(|&(_, _, cost)| cost).sum();
+        if !treasury.can_afford(cost) {
+            info!("Not enough funds for cable: need ${:.0}", cost);
+            return;
+        }
+        treasury.spend(Account::Construction, cost);
+        let placed = new_tiles
+            .into_iter()
+            .map(|(x, y, cost)| {
+                let cable = PowerCable { x, y, cost };
+                (buildings::spawn_cable(&mut commands, cable.clone()), Structure::Cable(cable))
+            })
+            .collect();
+        history.record(Action::Build { placed, cost });
+    }
+}
+
+/// Side panel for the building picked with the select tool
+pub fn building_inspector(
+    mut contexts: EguiContexts,
+    mut inspected: ResMut<InspectedBuilding>,
+    mut buildings: Query<(&mut Building, &BuildingStatus)>,
+    treasury: Option<ResMut<Treasury>>,
+    failed: Option<Res<MissionFailed>>,
+    mut demolish: EventWriter<Demolish>,
+) {
+    let Some(entity) = inspected.entity else {
+        return;
+    };
+    let (Ok((mut building, status)), Some(mut treasury)) = (buildings.get_mut(entity), treasury) else {
+        inspected.entity = None;
+        return;
+    };
+
+    let spending = failed.is_none();
+    let mut open = true;
+    egui::SidePanel::right("inspector").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.heading(building.kind.name());
+            if ui.small_button("✖").clicked() {
+                open = false;
+            }
+        });
+        ui.label(format!("Level {} at ({}, {})", building.level, building.x, building.y));
+        ui.separator();
+
+        ui.label(format!("Health: {:.0}%", building.health));
+        if building.broken {
+            ui.colored_label(egui::Color32::RED, "Broken down: repair to restart it");
+        }
+        let power = building.power();
+        if !building.enabled {
+            ui.label("Power: switched off");
+        } else if power >= 0.0 {
+            ui.label(format!("Power: produces {:.0} kW", power));
+        } else {
+            let state = if status.powered { "supplied" } else { "no supply" };
+            ui.label(format!("Power: draws {:.0} kW ({})", -power, state));
+        }
+        let crew = building.kind.crew();
+        if crew > 0 {
+            let on_shift = if status.staffed { crew } else { 0 };
+            ui.label(format!("Staff: {}/{}", on_shift, crew));
+        }
+        if building.kind.housing() > 0 {
+            ui.label(format!("Housing: {} crew", building.kind.housing()));
+        }
+        if let Some((product, _)) = building.kind.product() {
+            ui.label(format!("Output: {:.1} {}/day", status.output, product.unit()));
+            if building.storage() > 0.0 {
+                ui.label(format!("Storage: {:.0}/{:.0} {}", building.stored, building.storage(), product.unit()));
+            }
+        }
+        ui.label(format!("Upkeep: ${:.0}/day", building.upkeep()));
+        ui.separator();
+
+        let toggle = if building.enabled { "Switch off" } else { "Switch on" };
+        if ui.button(toggle).clicked() {
+            building.enabled = !building.enabled;
+        }
+        match building.upgrade_cost() {
+            Some(cost) => {
+                let label = format!("Upgrade (${:.0})", cost);
+                if ui.add_enabled(spending && treasury.can_afford(cost), egui::Button::new(label)).clicked() {
+                    treasury.spend(Account::Construction, cost);
+                    building.invested += cost;
+                    building.level += 1;
+                }
+            }
+            None => {
+                ui.add_enabled(false, egui::Button::new("Fully upgraded"));
+            }
+        }
+        let repair = building.repair_cost();
+        let can_repair = spending && (building.health < 100.0 || building.broken) && treasury.can_afford(repair);
+        if ui.add_enabled(can_repair, egui::Button::new(format!("Repair (${:.0})", repair))).clicked() {
+            treasury.spend(Account::Maintenance, repair);
+            building.repair();
+        }
+        let refund = building.demolition_refund();
+        if ui.add_enabled(spending, egui::Button::new(format!("Demolish (refund ${:.0})", refund))).clicked() {
+            demolish.send(Demolish { entity });
+            open = false;
+        }
+    });
+    if !open {
+        inspected.entity = None;
+    }
+}
+
+/// Seconds a notice stays on screen
+const NOTICE_SECONDS: f32 = 6.0;
+
+/// Short-lived notices in the bottom corner when construction finishes or
+/// a random event strikes
+pub fn notices(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    mut finished: EventReader<ConstructionFinished>,
+    mut random_events: EventReader<RandomEvent>,
+    mut notices: Local<Vec<(String, f32)>>,
+) {
+    let now = time.elapsed_seconds();
+    for event in finished.read() {
+        let text = format!("🏗 {} finished at ({}, {})", event.kind.name(), event.x, event.y);
+        notices.push((text, now + NOTICE_SECONDS));
+    }
+    for event in random_events.read() {
+        let icon = if event.is_disaster() { "⚠" } else { "★" };
+        notices.push((format!("{} {}", icon, event.description()), now + NOTICE_SECONDS));
+    }
+    notices.retain(|&(_, expires)| expires > now);
+    if notices.is_empty() {
+        return;
+    }
+
+    egui::Area::new("notices")
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -40.0))
+        .show(contexts.ctx_mut(), |ui| {
+            for (text, _) in notices.iter() {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(text);
+                });
+            }
+        });
+}
+
+/// Pixels per second the news ticker scrolls by
+const TICKER_SPEED: f32 = 60.0;
+
+/// Gap between stories on the ticker
+const TICKER_GAP: f32 = 40.0;
+
+/// Scrolling news along the bottom of the screen. It stops under the mouse,
+/// and clicking a story with a location centers the map on it.
+pub fn news_ticker(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    ticker: Option<ResMut<Ticker>>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+) {
+    let Some(mut ticker) = ticker else {
+        return;
+    };
+    let mut target = None;
+    egui::TopBottomPanel::bottom("news_ticker").exact_height(22.0).show(contexts.ctx_mut(), |ui| {
+        let rect = ui.max_rect();
+        let hovered = ui.rect_contains_pointer(rect);
+        let mut x = rect.left() + ticker.offset;
+        for (index, (day, news)) in ticker.items.iter().enumerate() {
+            let color = match news.kind {
+                NewsKind::Disaster => egui::Color32::LIGHT_RED,
+                NewsKind::Opportunity | NewsKind::Objective => egui::Color32::LIGHT_GREEN,
+                _ => ui.visuals().text_color(),
+            };
+            let text = format!("{} Day {}: {}", news.kind.icon(), day + 1, news.text);
+            let galley = ui.painter().layout_no_wrap(text, egui::FontId::proportional(14.0), color);
+            let size = galley.size();
+            let item = egui::Rect::from_min_size(egui::pos2(x, rect.center().y - size.y / 2.0), size);
+            x += size.x + TICKER_GAP;
+            if !item.intersects(rect) {
+                continue;
+            }
+            ui.painter().galley(item.min, galley);
+            if let Some(location) = news.location {
+                let response = ui.interact(item, ui.id().with(index), egui::Sense::click());
+                if response.on_hover_cursor(egui::CursorIcon::PointingHand).clicked() {
+                    target = Some(location);
+                }
+            }
+        }
+
+        let length = x - rect.left() - ticker.offset;
+        if !hovered {
+            ticker.offset -= TICKER_SPEED * time.delta_seconds();
+        }
+        if ticker.offset < -length {
+            ticker.offset = rect.width();
+        }
+    });
+
+    if let (Some((x, y)), Ok(mut transform)) = (target, camera.get_single_mut()) {
+        transform.translation.x = x as f32 * 10.0;
+        transform.translation.y = y as f32 * 10.0;
+    }
+}
+
+/// Largest side of the minimap in pixels; bigger maps are sampled down
+const MINIMAP_SIZE: usize = 256;
+
+/// Render the whole map to an image, one pixel per sampled tile with north up
+fn minimap_image(tile_map: &TileMap) -> egui::ColorImage {
+    let step = (tile_map.width.max(tile_map.height) as usize).div_ceil(MINIMAP_SIZE).max(1);
+    // Round up so the last partial block of tiles still gets a pixel and the
+    // image keeps the map's proportions
+    let (width, height) = ((tile_map.width as usize).div_ceil(step), (tile_map.height as usize).div_ceil(step));
+    let relief = relief(tile_map);
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in 0..height {
+        for column in 0..width {
+            let x = ((column * step) as u32).min(tile_map.width - 1);
+            let y = (((height - 1 - row) * step) as u32).min(tile_map.height - 1);
+            let terrain = tile_map.terrain(x, y).unwrap_or(TerrainType::Flat);
+            let color = tile_color(terrain, tile_map.elevation(x, y).unwrap_or(0.0), relief, true);
+            let [r, g, b, _] = color.as_rgba_u8();
+            pixels.push(egui::Color32::from_rgb(r, g, b));
+        }
+    }
+    egui::ColorImage { size: [width, height], pixels }
+}
+
+/// The whole map in miniature with buildings and the camera's view marked.
+/// Clicking or dragging on it moves the camera there, and the overlay picked
+/// below tints the main map.
+pub fn map_view(
+    mut contexts: EguiContexts,
+    mut window: ResMut<MapViewWindow>,
+    mut display: ResMut<MapDisplay>,
+    tile_map: Option<Res<TileMap>>,
+    mut texture: Local<Option<(u64, egui::TextureHandle)>>,
+    mut camera: Query<(&mut Transform, &OrthographicProjection), With<Camera2d>>,
+    buildings: Query<&Building>,
+) {
+    let Some(tile_map) = tile_map else {
+        return;
+    };
+    if !window.open {
+        return;
+    }
+    let ctx = contexts.ctx_mut().clone();
+    // Redraw only when tiles change or a new map replaces the old one
+    if tile_map.is_added() || texture.as_ref().map(|(revision, _)| *revision) != Some(tile_map.revision()) {
+        let handle = ctx.load_texture("minimap", minimap_image(&tile_map), egui::TextureOptions::NEAREST);
+        *texture = Some((tile_map.revision(), handle));
+    }
+    let Some((_, texture)) = texture.as_ref() else {
+        return;
+    };
+
+    let (map_width, map_height) = (tile_map.width as f32 * 10.0, tile_map.height as f32 * 10.0);
+    let scale = MINIMAP_SIZE as f32 / map_width.max(map_height);
+    let mut overlay = display.overlay;
+    let mut target = None;
+    egui::Window::new("Map View").open(&mut window.open).resizable(false).show(&ctx, |ui| {
+        let size = egui::vec2(map_width * scale, map_height * scale);
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::click_and_drag());
+        let rect = response.rect;
+        // Tile n spans 10n - 5 to 10n + 5 in world space
+        let to_screen = |world: Vec2| {
+            egui::pos2(rect.left() + (world.x + 5.0) * scale, rect.bottom() - (world.y + 5.0) * scale)
+        };
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(texture.id(), rect, uv, egui::Color32::WHITE);
+
+        let marker = (10.0 * scale).max(2.0);
+        for building in buildings.iter() {
+            let center = to_screen(Vec2::new(building.x as f32 * 10.0, building.y as f32 * 10.0));
+            let [r, g, b, _] = building.kind.color().as_rgba_u8();
+            let footprint = egui::Rect::from_center_size(center, egui::vec2(marker, marker));
+            painter.rect_filled(footprint, 0.0, egui::Color32::from_rgb(r, g, b));
+        }
+        if let Ok((transform, projection)) = camera.get_single() {
+            let view = camera_view(transform, projection);
+            let viewport = egui::Rect::from_two_pos(to_screen(view.min), to_screen(view.max));
+            painter.rect_stroke(viewport.intersect(rect), 0.0, egui::Stroke::new(1.5, egui::Color32::WHITE));
+        }
+        if response.clicked() || response.dragged() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let offset = egui::vec2(pointer.x - rect.left(), rect.bottom() - pointer.y) / scale;
+                target = Some(Vec2::new(offset.x - 5.0, offset.y - 5.0));
+            }
+        }
+
+        ui.separator();
+        ui.label("Overlay");
+        for choice in Overlay::ALL {
+            ui.radio_value(&mut overlay, choice, choice.name());
+        }
+    });
+
+    // Only touch the display when the overlay changes, as that re-meshes the map
+    if overlay != display.overlay {
+        display.overlay = overlay;
+    }
+    if let (Some(world), Ok((mut transform, _))) = (target, camera.get_single_mut()) {
+        transform.translation.x = world.x.clamp(0.0, map_width);
+        transform.translation.y = world.y.clamp(0.0, map_height);
+    }
+}
+
+/// Every random event of the mission, newest first
+pub fn event_log(mut contexts: EguiContexts, mut window: ResMut<EventLogWindow>, log: Option<Res<EventLog>>) {
+    let Some(log) = log else {
+        return;
+    };
+    egui::Window::new("Event Log").open(&mut window.open).show(contexts.ctx_mut(), |ui| {
+        if log.entries.is_empty() {
+            ui.label("Nothing out of the ordinary yet.");
+        }
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            for (day, description) in log.entries.iter().rev() {
+                ui.label(format!("Day {}: {}", day + 1, description));
+            }
+        });
+    });
+}
+
+/// Days of oxygen below which Mission Control raises an alert
+const OXYGEN_ALERT_DAYS: f32 = 10.0;
+
+/// Where a Mission Control widget leads
+enum Link {
+    Inspect(Entity),
+    FinancialReport,
+    TradeCenter,
+    EventLog,
+}
+
+/// Add a small button that follows `link` when clicked
+fn link_button(ui: &mut egui::Ui, label: &str, link: Link, followed: &mut Option<Link>) {
+    if ui.small_button(format!("{} ›", label)).clicked() {
+        *followed = Some(link);
+    }
+}
+
+/// One-glance summary of the base, with each widget leading on to the
+/// window or building behind it
+pub fn mission_control(
+    mut contexts: EguiContexts,
+    mut windows: Windows,
+    mut inspected: ResMut<InspectedBuilding>,
+    mission: Option<Res<Mission>>,
+    completed: Option<Res<CompletedObjectives>>,
+    workforce: Option<Res<Workforce>>,
+    research: Option<Res<Research>>,
+    life_support: Option<Res<LifeSupport>>,
+    stockpile: Option<Res<Stockpile>>,
+    shipping: Option<Res<Shipping>>,
+    funding: Option<Res<Funding>>,
+    flare: Option<Res<SolarFlare>>,
+    buildings: Query<(Entity, &Building, &BuildingStatus)>,
+) {
+    let (
+        Some(mission),
+        Some(completed),
+        Some(workforce),
+        Some(research),
+        Some(life_support),
+        Some(stockpile),
+        Some(shipping),
+        Some(funding),
+    ) = (mission, completed, workforce, research, life_support, stockpile, shipping, funding)
+    else {
+        return;
+    };
+    let first = |kind: BuildingKind| buildings.iter().find(|(_, b, _)| b.kind == kind).map(|(entity, _, _)| entity);
+    let product_totals = |product: Product| {
+        buildings
+            .iter()
+            .filter(|(_, building, _)| building.kind.product().is_some_and(|(p, _)| p == product))
+            .fold((0.0, 0.0, 0.0), |(stored, capacity, output), (_, building, status)| {
+                (stored + building.stored, capacity + building.storage(), output + status.output)
+            })
+    };
+
+    let mut followed = None;
+    let mut open = windows.mission_control.open;
+    egui::Window::new("Mission Control").open(&mut open).resizable(false).show(contexts.ctx_mut(), |ui| {
+        ui.columns(2, |columns| {
+            let ui = &mut columns[0];
+
+            ui.group(|ui| {
+                ui.strong("Population");
+                let housing = LANDER_HOUSING + buildings.iter().map(|(_, b, _)| b.kind.housing()).sum::<u32>();
+                ui.label(format!("Crew: {} of {} bunks", workforce.crew, housing));
+                ui.label(format!("Bulldozers: {}", workforce.bulldozers));
+                ui.label(format!("On the way: {}, requested: {}", shipping.crew_inbound(), shipping.crew_wanted));
+                link_button(ui, "Shuttles", Link::TradeCenter, &mut followed);
+            });
+
+            ui.group(|ui| {
+                ui.strong("Oxygen");
+                let (stored, _, production) = product_totals(Product::Oxygen);
+                let reserve = stored + life_support.lander_reserve;
+                ui.label(format!("Reserve: {:.0} kg", reserve));
+                ui.label(format!(
+                    "Produced {:.0} kg/day, breathed {:.0} kg/day",
+                    production,
+                    workforce.crew as f32 * OXYGEN_PER_CREW
+                ));
+                match oxygen_days_left(reserve, production, workforce.crew) {
+                    Some(days) if days < OXYGEN_ALERT_DAYS => {
+                        ui.colored_label(egui::Color32::LIGHT_RED, format!("{:.0} days left", days));
+                    }
+                    Some(days) => {
+                        ui.label(format!("{:.0} days left", days));
+                    }
+                    None => {
+                        ui.colored_label(egui::Color32::LIGHT_GREEN, "Sustainable");
+                    }
+                }
+                if let Some(plant) = first(BuildingKind::OxygenPlant) {
+                    link_button(ui, "Oxygen plant", Link::Inspect(plant), &mut followed);
+                }
+            });
+
+            ui.group(|ui| {
+                ui.strong("Power");
+                let (supply, demand) = buildings
+                    .iter()
+                    .filter(|(_, _, status)| status.staffed)
+                    .map(|(_, building, _)| building.power())
+                    .fold((0.0, 0.0), |(supply, demand), power| {
+                        if power > 0.0 { (supply + power, demand) } else { (supply, demand - power) }
+                    });
+                let color = if supply >= demand { egui::Color32::LIGHT_GREEN } else { egui::Color32::LIGHT_RED };
+                ui.colored_label(color, format!("{:.0} kW supplied, {:.0} kW drawn", supply, demand));
+                let dark = buildings.iter().find(|(_, building, status)| building.operating() && !status.powered);
+                if let Some((entity, building, _)) = dark {
+                    let label = format!("{} without power", building.kind.name());
+                    link_button(ui, &label, Link::Inspect(entity), &mut followed);
+                } else if let Some(solar) = first(BuildingKind::SolarArray) {
+                    link_button(ui, "Solar array", Link::Inspect(solar), &mut followed);
+                }
+            });
+
+            let ui = &mut columns[1];
+
+            ui.group(|ui| {
+                ui.strong("Storage");
+                for product in [Product::Oxygen, Product::Helium3] {
+                    let (stored, capacity, _) = product_totals(product);
+                    if capacity > 0.0 {
+                        let text = format!("{:.0} {}", stored, product.unit());
+                        ui.add(egui::ProgressBar::new(stored / capacity).text(text));
+                    }
+                }
+                for commodity in Commodity::ALL {
+                    let held = stockpile.get(commodity);
+                    if held > 0.0 {
+                        ui.label(format!("{}: {:.1} {}", commodity.name(), held, commodity.unit()));
+                    }
+                }
+                link_button(ui, "Trade Center", Link::TradeCenter, &mut followed);
+            });
+
+            ui.group(|ui| {
+                ui.strong("Research");
+                ui.label(format!("{:.0} points", research.points));
+                if !research.crater_filling {
+                    let share = research.points / CRATER_FILLING_POINTS;
+                    ui.add(egui::ProgressBar::new(share).text("Crater filling"));
+                }
+                if let Some(lab) = first(BuildingKind::ScienceLab) {
+                    link_button(ui, "Science lab", Link::Inspect(lab), &mut followed);
+                }
+            });
+
+            ui.group(|ui| {
+                ui.strong("Objectives");
+                for (index, objective) in mission.objectives.iter().enumerate() {
+                    match completed.day(index) {
+                        Some(day) => ui.label(format!("✔ {} (day {})", objective.description, day + 1)),
+                        None => ui.label(format!("○ {}", objective.description)),
+                    };
+                }
+                link_button(ui, "Grants", Link::FinancialReport, &mut followed);
+            });
+        });
+
+        ui.separator();
+        ui.strong("Alerts");
+        let mut alerts = 0;
+        let mut alert = |ui: &mut egui::Ui, text: String, link: Option<Link>, followed: &mut Option<Link>| {
+            alerts += 1;
+            ui.horizontal(|ui| {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("⚠ {}", text));
+                if let Some(link) = link {
+                    link_button(ui, "Show", link, followed);
+                }
+            });
+        };
+        for (entity, building, _) in buildings.iter().filter(|(_, building, _)| building.broken) {
+            let text = format!("{} at ({}, {}) has broken down", building.kind.name(), building.x, building.y);
+            alert(ui, text, Some(Link::Inspect(entity)), &mut followed);
+        }
+        let (stored, _, production) = product_totals(Product::Oxygen);
+        let reserve = stored + life_support.lander_reserve;
+        if let Some(days) = oxygen_days_left(reserve, production, workforce.crew).filter(|&d| d < OXYGEN_ALERT_DAYS) {
+            alert(ui, format!("Oxygen runs out in {:.0} days", days), None, &mut followed);
+        }
+        if funding.days_in_red > 0 {
+            let text = format!("Funds negative for {} of {} days", funding.days_in_red, mission.grace_days);
+            alert(ui, text, Some(Link::FinancialReport), &mut followed);
+        }
+        if shipping.flights.iter().any(|flight| flight.stage == logistics::Stage::Holding) {
+            alert(ui, "A shuttle is holding in orbit".to_string(), Some(Link::TradeCenter), &mut followed);
+        }
+        if let Some(flare) = &flare {
+            alert(ui, format!("Solar flare: crew sheltering for {} days", flare.days_left), None, &mut followed);
+        }
+        if alerts == 0 {
+            ui.colored_label(egui::Color32::LIGHT_GREEN, "All systems nominal");
+        }
+        link_button(ui, "Event log", Link::EventLog, &mut followed);
+    });
+    windows.mission_control.open = open;
+
+    match followed {
+        Some(Link::Inspect(entity)) => inspected.entity = Some(entity),
+        Some(Link::FinancialReport) => windows.financial_report.open = true,
+        Some(Link::TradeCenter) => windows.trade_center.open = true,
+        Some(Link::EventLog) => windows.event_log.open = true,
+        None => {}
+    }
+}
+
+/// Tell the player the mission is over and offer a fresh start
+pub fn mission_failed(
+    mut contexts: EguiContexts,
+    failed: Option<Res<MissionFailed>>,
+    mut new_game_dialog: ResMut<NewGameDialog>,
 ) {
-    if let Some(tool) = tool {
-        if buttons.just_pressed(MouseButton::Left) {
-            if let Ok(window) = windows.get_single() {
-                if let Some(position) = window.cursor_position() {
-                    // Handle tool placement at cursor position
-                    println!("Placing {:?} at {:?}", tool.tool, position);
+    let Some(failed) = failed else {
+        return;
+    };
+    egui::Window::new("Mission Failed")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(&failed.reason);
+            if ui.button("New Game").clicked() {
+                new_game_dialog.open = true;
+            }
+        });
+}
+
+/// Transactions listed under the income statement
+const RECENT_TRANSACTIONS: usize = 12;
+
+/// Monthly income statement, cash-flow chart and projection
+pub fn financial_report(
+    mut contexts: EguiContexts,
+    mut report: ResMut<FinancialReport>,
+    treasury: Option<ResMut<Treasury>>,
+    funding: Option<ResMut<Funding>>,
+    mission: Option<Res<Mission>>,
+    failed: Option<Res<MissionFailed>>,
+) {
+    let (Some(mut treasury), Some(mut funding), Some(mission)) = (treasury, funding, mission) else {
+        return;
+    };
+    let spending = failed.is_none();
+    let current_month = treasury.day() / DAYS_PER_MONTH;
+    report.month = report.month.min(current_month);
+
+    let mut open = report.open;
+    egui::Window::new("Financial Report").open(&mut open).resizable(false).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            if ui.add_enabled(report.month > 0, egui::Button::new("◀")).clicked() {
+                report.month -= 1;
+            }
+            ui.strong(format!("Month {}", report.month + 1));
+            if ui.add_enabled(report.month < current_month, egui::Button::new("▶")).clicked() {
+                report.month += 1;
+            }
+        });
+
+        let statement = treasury.statement(report.month);
+        egui::Grid::new("income_statement").num_columns(2).striped(true).show(ui, |ui| {
+            for (heading, income) in [("Income", true), ("Expenses", false)] {
+                ui.strong(heading);
+                ui.end_row();
+                for &(account, amount) in statement.lines.iter().filter(|&&(_, amount)| (amount > 0.0) == income) {
+                    ui.label(format!("  {}", account.name()));
+                    ui.label(format!("${:.0}", amount.abs()));
+                    ui.end_row();
                 }
             }
+            ui.strong("Net");
+            ui.strong(format!("${:.0}", statement.net()));
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label("Cash flow");
+        let mut balances = treasury.closing_balances().to_vec();
+        balances.push(treasury.balance());
+        let projection = (DAYS_PER_MONTH as usize, treasury.projection(DAYS_PER_MONTH));
+        line_chart(ui, &balances, Some(projection), Some(0.0), egui::Color32::LIGHT_GREEN);
+        ui.label(format!(
+            "Projected balance in {} days: ${:.0} ({:+.0}/day)",
+            DAYS_PER_MONTH,
+            treasury.projection(DAYS_PER_MONTH),
+            treasury.daily_trend()
+        ));
+
+        ui.separator();
+        ui.strong("Loans");
+        let credit = funding.credit_available(&mission, &treasury);
+        ui.label(format!("Owed: ${:.0}   Available credit: ${:.0}", funding.debt(), credit));
+        report.loan_amount = report.loan_amount.min(credit);
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut report.loan_amount, 0.0..=credit).prefix("$"));
+            let label = format!("Borrow at {:.0}%", mission.interest_rate * 100.0);
+            let button = ui
+                .add_enabled(spending && report.loan_amount >= 1.0, egui::Button::new(label))
+                .on_hover_text(format!("Repaid daily over {} days", funding::LOAN_TERM_DAYS));
+            if button.clicked() {
+                funding.borrow(&mut treasury, report.loan_amount, mission.interest_rate);
+                report.loan_amount = 0.0;
+            }
+        });
+        let mut repay = None;
+        for (index, loan) in funding.loans.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "${:.0} left, {} days, ${:.0}/day",
+                    loan.principal,
+                    loan.days_left,
+                    loan.daily_repayment() + loan.daily_interest()
+                ));
+                let can_repay = spending && treasury.can_afford(loan.principal);
+                if ui.add_enabled(can_repay, egui::Button::new("Repay")).clicked() {
+                    repay = Some(index);
+                }
+            });
         }
+        if let Some(index) = repay {
+            funding.repay(&mut treasury, index);
+        }
+        if funding.days_in_red > 0 {
+            ui.colored_label(
+                egui::Color32::LIGHT_RED,
+                format!("Funds negative for {} of {} days allowed", funding.days_in_red, mission.grace_days),
+            );
+        }
+
+        egui::CollapsingHeader::new("Grants").show(ui, |ui| {
+            for (index, grant) in mission.grants.iter().enumerate() {
+                let status = if funding.awarded(index) { "✔" } else { "○" };
+                ui.label(format!(
+                    "{} {}: ${:.0} from {}",
+                    status,
+                    grant.milestone.description(),
+                    grant.amount,
+                    grant.sponsor
+                ));
+            }
+        });
+
+        egui::CollapsingHeader::new("Recent transactions").show(ui, |ui| {
+            for transaction in treasury.transactions().iter().rev().take(RECENT_TRANSACTIONS) {
+                ui.label(format!(
+                    "Day {}: {} {:+.0}",
+                    transaction.day + 1,
+                    transaction.account.name(),
+                    transaction.amount
+                ));
+            }
+        });
+    });
+    report.open = open;
+}
+
+/// Plot `values` one per day, optionally dashing on to a projected value some
+/// days ahead, and with a baseline such as zero kept in view
+fn line_chart(
+    ui: &mut egui::Ui,
+    values: &[f32],
+    projection: Option<(usize, f32)>,
+    baseline: Option<f32>,
+    color: egui::Color32,
+) {
+    let (response, painter) = ui.allocate_painter(egui::vec2(360.0, 140.0), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(24));
+    let (Some(&last), Some(&first)) = (values.last(), values.first()) else {
+        return;
+    };
+
+    let ahead = projection.map_or(0, |(days, _)| days);
+    let days = (values.len() + ahead - 1).max(1) as f32;
+    let (min, max) = values
+        .iter()
+        .copied()
+        .chain(projection.map(|(_, value)| value))
+        .chain(baseline)
+        .fold((first, first), |(min, max), value| (min.min(value), max.max(value)));
+    let span = (max - min).max(1.0);
+    let to_screen = |day: f32, value: f32| {
+        egui::pos2(rect.left() + rect.width() * day / days, rect.bottom() - rect.height() * (value - min) / span)
+    };
+
+    if let Some(baseline) = baseline {
+        let stroke = egui::Stroke::new(1.0, egui::Color32::GRAY);
+        painter.line_segment([to_screen(0.0, baseline), to_screen(days, baseline)], stroke);
     }
-} 
+    let line = values.iter().enumerate().map(|(day, &value)| to_screen(day as f32, value)).collect();
+    painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, color)));
+    if let Some((_, projected)) = projection {
+        let today = (values.len() - 1) as f32;
+        painter.extend(egui::Shape::dashed_line(
+            &[to_screen(today, last), to_screen(days, projected)],
+            egui::Stroke::new(1.0, egui::Color32::YELLOW),
+            4.0,
+            3.0,
+        ));
+    }
+}
+
+/// Sell exports and buy imports at Earth prices, with a price history graph
+pub fn trade_center(
+    mut contexts: EguiContexts,
+    mut window: ResMut<TradeCenter>,
+    market: Option<ResMut<Market>>,
+    stockpile: Option<ResMut<Stockpile>>,
+    treasury: Option<ResMut<Treasury>>,
+    shipping: Option<ResMut<Shipping>>,
+    clock: Option<Res<GameClock>>,
+    failed: Option<Res<MissionFailed>>,
+    mut buildings: Query<&mut Building>,
+) {
+    let (Some(mut market), Some(mut stockpile), Some(mut treasury), Some(mut shipping), Some(clock)) =
+        (market, stockpile, treasury, shipping, clock)
+    else {
+        return;
+    };
+    let spending = failed.is_none();
+
+    let mut open = window.open;
+    egui::Window::new("Trade Center").open(&mut open).resizable(false).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Quantity per trade:");
+            ui.add(egui::DragValue::new(&mut window.quantity).clamp_range(1.0..=1000.0).speed(1.0));
+        });
+        ui.separator();
+
+        let helium3: f32 = buildings
+            .iter()
+            .filter(|building| building.kind.product().is_some_and(|(product, _)| product == Product::Helium3))
+            .map(|building| building.stored)
+            .sum();
+        egui::Grid::new("market").num_columns(6).striped(true).show(ui, |ui| {
+            ui.strong("Commodity");
+            ui.strong("Price");
+            ui.strong("Change");
+            ui.strong("Held");
+            ui.strong("Queued");
+            ui.end_row();
+            for commodity in Commodity::ALL {
+                let selected = window.selected == commodity;
+                if ui.selectable_label(selected, commodity.name()).clicked() {
+                    window.selected = commodity;
+                }
+                let price = market.price(commodity);
+                ui.label(format!("${:.0}/{}", price, commodity.unit()));
+                let yesterday = market.history(commodity).rev().nth(1).unwrap_or(price);
+                let change = (price / yesterday - 1.0) * 100.0;
+                let color = if change >= 0.0 { egui::Color32::LIGHT_GREEN } else { egui::Color32::LIGHT_RED };
+                ui.colored_label(color, format!("{:+.1}%", change));
+
+                let held = if commodity == Commodity::Helium3 { helium3 } else { stockpile.get(commodity) };
+                ui.label(format!("{:.1} {}", held, commodity.unit()));
+                let (ordered, exporting) = shipping.queued(commodity);
+                ui.label(format!("{:.1}", ordered + exporting));
+                if commodity.exported() {
+                    let amount = window.quantity.min(held);
+                    let label = format!("Ship (~${:.0})", amount * market.sell_price(commodity));
+                    let button = ui
+                        .add_enabled(spending && amount > 0.0, egui::Button::new(label))
+                        .on_hover_text("Sold at the going price when the next shuttle takes it");
+                    if button.clicked() {
+                        let taken = if commodity == Commodity::Helium3 {
+                            market::take_stored(buildings.iter_mut(), Product::Helium3, amount)
+                        } else {
+                            stockpile.take(commodity, amount)
+                        };
+                        shipping.export(commodity, taken);
+                    }
+                } else {
+                    let cost = window.quantity * market.buy_price(commodity);
+                    let label = format!("Buy (${:.0})", cost);
+                    let can_buy = spending && window.quantity > 0.0 && treasury.can_afford(cost);
+                    let button = ui
+                        .add_enabled(can_buy, egui::Button::new(label))
+                        .on_hover_text(format!("{}. Paid now, delivered by shuttle", commodity.purpose()));
+                    if button.clicked() {
+                        treasury.spend(Account::Purchases, cost);
+                        shipping.order(commodity, window.quantity);
+                        market.trade(commodity, -window.quantity);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        let selected = window.selected;
+        ui.label(format!("{} price history", selected.name()));
+        let prices: Vec<f32> = market.history(selected).collect();
+        line_chart(ui, &prices, None, None, egui::Color32::LIGHT_BLUE);
+
+        ui.separator();
+        ui.strong("Shuttles");
+        let pads = buildings
+            .iter()
+            .filter(|building| building.kind == BuildingKind::LandingPad && building.operating())
+            .count();
+        if pads == 0 {
+            ui.colored_label(egui::Color32::LIGHT_RED, "No working landing pad: launches will be missed");
+        }
+        let refuelled = stockpile.get(Commodity::Fuel) >= logistics::FUEL_PER_SHUTTLE;
+        ui.label(format!(
+            "Next launch: day {} (${:.0} per launch{})",
+            shipping.next_launch.max(clock.day),
+            logistics::launch_cost(0, refuelled),
+            if refuelled { ", refuelled here" } else { "" }
+        ));
+        for flight in &shipping.flights {
+            let stage = match flight.stage {
+                logistics::Stage::Inbound { arrives } => format!("arriving day {}", arrives),
+                logistics::Stage::Holding => "holding in orbit, pads full".to_string(),
+                logistics::Stage::Landed { departs } => format!("on the pad, leaving day {}", departs),
+            };
+            ui.label(format!("Shuttle {}: {}", flight.id, stage));
+        }
+        ui.horizontal(|ui| {
+            ui.label("Crew requested:");
+            ui.add(egui::DragValue::new(&mut shipping.crew_wanted).clamp_range(0..=40));
+            ui.label(format!("(${:.0} fare each)", logistics::PASSENGER_FARE));
+        });
+    });
+    window.open = open;
+}